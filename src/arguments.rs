@@ -1,5 +1,51 @@
 use serde_json::Value;
-use std::{fs::read_to_string, path::PathBuf};
+use std::path::PathBuf;
+
+use crate::config::{ConfigFormat, SaaruConfig, ServerConfig, CONFIG_FILE_NAMES, DEFAULT_TEMPLATE};
+use crate::error::{Result, SaaruError};
+use crate::utils::resolve_path;
+
+// Directories given on the command line, which win over the ones in the config
+#[derive(Debug, Default)]
+pub struct DirectoryOverrides {
+    pub source: Option<PathBuf>,
+    pub templates: Option<PathBuf>,
+    pub static_files: Option<PathBuf>,
+    pub build: Option<PathBuf>,
+}
+
+// Where to serve the site, given on the command line, winning over `server` in the config
+#[derive(Debug, Default, Clone)]
+pub struct ServerOverrides {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub prefix: Option<String>,
+}
+
+impl ServerOverrides {
+    fn apply(&self, server: &mut ServerConfig) {
+        if let Some(host) = &self.host {
+            server.address = host.clone();
+        }
+        if let Some(port) = self.port {
+            server.port = port;
+        }
+        if let Some(prefix) = &self.prefix {
+            server.prefix = Some(prefix.clone());
+        }
+    }
+}
+
+// Command line switches that change how the site gets built
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BuildOptions {
+    pub live_reload: bool,
+    pub live_rerender: bool,
+    pub keep_going: bool,
+    pub drafts: bool,
+    pub no_cache: bool,
+    pub clean: bool,
+}
 
 #[derive(Debug)]
 pub struct SaaruArguments {
@@ -8,40 +54,55 @@ pub struct SaaruArguments {
     pub source_dir: PathBuf,
     pub static_dir: PathBuf,
     pub build_dir: PathBuf,
+    // The raw config, handed to templates as `base.json`
     pub json_content: Value,
+    // The same config, typed and validated
+    pub config: SaaruConfig,
+    pub config_path: PathBuf,
+    // Kept to apply again whenever the config is reloaded
+    server_overrides: ServerOverrides,
     // Arguments for Live Reload and so on
     pub live_reload: bool,
     pub live_rerender: bool,
+    // Keep going past per-file failures to report them all at the end, without writing anything
+    pub keep_going: bool,
+    // Render pages marked `wip: true` instead of leaving them out
+    pub drafts: bool,
+    // Ignore the build cache and render everything from scratch
+    pub no_cache: bool,
+    // Empty the build directory before building, instead of only pruning stale outputs
+    pub clean: bool,
 }
 
 impl SaaruArguments {
-    pub fn new(mut base_dir: PathBuf, live_reload: bool, live_rerender: bool) -> Self {
+    pub fn new(
+        mut base_dir: PathBuf,
+        config_path: Option<PathBuf>,
+        overrides: DirectoryOverrides,
+        server_overrides: ServerOverrides,
+        options: BuildOptions,
+    ) -> Result<Self> {
         log::info!("Initializing Arguments");
 
-        base_dir = std::fs::canonicalize(base_dir).unwrap();
+        base_dir = std::fs::canonicalize(&base_dir).map_err(|e| SaaruError::io(&base_dir, e))?;
 
-        let mut template_path = PathBuf::from(&base_dir);
-        let mut static_path = PathBuf::from(&base_dir);
-        let mut content_path = PathBuf::from(&base_dir);
-        let mut build_path = PathBuf::from(&base_dir);
-        let json_path = PathBuf::from(&base_dir).join(".saaru.json");
-
-        template_path.push("templates/");
-        static_path.push("static");
-        content_path.push("src");
-        build_path.push("build");
-        log::info!("Initalized Arguments from Base Path {:?}", &base_dir);
+        // An explicit config file wins, otherwise look for one in the base path
+        let config_path = match config_path {
+            Some(path) => Some(resolve_path(&base_dir, &path)),
+            None => SaaruConfig::discover(&base_dir)?,
+        };
 
-        // Read the JSON
-        // TODO Validate this
-        let raw_json_content = match read_to_string(json_path) {
-            Ok(content) => serde_json::from_str(&content).unwrap(),
-            Err(_) => {
-                // TODO better error handling here - check for another issue that
-                // probably isn't the "didn't find file" error
-                log::error!("Couldn't find .saaru.json in {:?}", &base_dir);
+        let (mut config, json_content, config_path) = match config_path {
+            Some(path) => {
+                log::info!("Reading config from {:?}", &path);
+                let (config, json_content) = SaaruConfig::from_file(&path)?;
+                (config, json_content, path)
+            }
+            None => {
+                log::error!("Couldn't find a config file in {:?}", &base_dir);
                 log::warn!("Using default values!");
-                serde_json::json!({
+                let json_path = base_dir.join(CONFIG_FILE_NAMES[0]);
+                let default_json = serde_json::json!({
                   "metadata": {
                     "author": {
                       "name": "Author",
@@ -50,24 +111,87 @@ impl SaaruArguments {
                       "github": "github.com/username",
                     },
                     "templates": {
-                        "default": "post.json",
+                        "default": DEFAULT_TEMPLATE,
                     }
                   }
-                })
+                });
+                let (config, json_content) = SaaruConfig::from_str(
+                    &default_json.to_string(),
+                    ConfigFormat::Json,
+                    &json_path,
+                )?;
+                (config, json_content, json_path)
             }
         };
-        let json_content = raw_json_content;
         log::info!("Finished Reading JSON Content -> {:?}", json_content);
 
-        SaaruArguments {
+        // Command line flags win over the config
+        let directories = &mut config.directories;
+        if let Some(source) = overrides.source {
+            directories.source = source;
+        }
+        if let Some(templates) = overrides.templates {
+            directories.templates = templates;
+        }
+        if let Some(static_files) = overrides.static_files {
+            directories.static_files = static_files;
+        }
+        if let Some(build) = overrides.build {
+            directories.build = build;
+        }
+        server_overrides.apply(&mut config.server);
+
+        // Relative directories are relative to the base path, absolute ones are left alone
+        let template_path = resolve_path(&base_dir, &directories.templates);
+        let static_path = resolve_path(&base_dir, &directories.static_files);
+        let content_path = resolve_path(&base_dir, &directories.source);
+        let build_path = resolve_path(&base_dir, &directories.build);
+        log::info!("Initalized Arguments from Base Path {:?}", &base_dir);
+
+        Ok(SaaruArguments {
             base_dir,
             template_dir: template_path,
             source_dir: content_path,
             static_dir: static_path,
             build_dir: build_path,
             json_content,
-            live_reload,
-            live_rerender,
+            config,
+            config_path,
+            server_overrides,
+            live_reload: options.live_reload,
+            live_rerender: options.live_rerender,
+            keep_going: options.keep_going,
+            drafts: options.drafts,
+            no_cache: options.no_cache,
+            clean: options.clean,
+        })
+    }
+
+    // Read the config file again while live re-rendering. Nothing changes if it's broken.
+    // The directories are already being watched, so moving them needs a restart.
+    pub fn reload_config(&mut self) -> Result<()> {
+        let (mut config, json_content) = SaaruConfig::from_file(&self.config_path)?;
+        if json_content.get("directories") != self.json_content.get("directories") {
+            log::warn!(
+                "Directory changes in {:?} take effect after a restart",
+                &self.config_path
+            );
+        }
+        config.directories = self.config.directories.clone();
+        // The server is already listening, so it keeps going as it is. Flags still win
+        // over the file, so only changes that get past them count.
+        self.server_overrides.apply(&mut config.server);
+        if config.server != self.config.server {
+            log::warn!(
+                "Server changes in {:?} take effect after a restart",
+                &self.config_path
+            );
         }
+        config.server = self.config.server.clone();
+
+        log::info!("Reloaded config from {:?}", &self.config_path);
+        self.config = config;
+        self.json_content = json_content;
+        Ok(())
     }
 }
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::error::{Result, SaaruError};
+
+pub const CACHE_DIR: &str = ".saaru-cache";
+const CACHE_FILE: &str = "build.json";
+const MANIFEST_FILE: &str = "manifest.json";
+// Bump this whenever the way fingerprints are computed changes
+const CACHE_VERSION: u32 = 1;
+
+// What went into every output of the last build, so the next build can skip
+// whatever hasn't changed. Lives in `.saaru-cache/` under the base path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildCache {
+    version: u32,
+    // Build-relative output path -> fingerprint of everything it was rendered from
+    pub outputs: HashMap<String, String>,
+    // Static-relative path -> hash of the file's contents
+    pub static_files: HashMap<String, String>,
+}
+
+impl Default for BuildCache {
+    fn default() -> Self {
+        BuildCache {
+            version: CACHE_VERSION,
+            outputs: HashMap::new(),
+            static_files: HashMap::new(),
+        }
+    }
+}
+
+impl BuildCache {
+    pub fn path(base_dir: &Path) -> PathBuf {
+        base_dir.join(CACHE_DIR).join(CACHE_FILE)
+    }
+
+    // A missing or unreadable cache just means everything gets rebuilt
+    pub fn load(base_dir: &Path) -> Self {
+        let path = BuildCache::path(base_dir);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return BuildCache::default(),
+        };
+        match serde_json::from_str::<BuildCache>(&content) {
+            Ok(cache) if cache.version == CACHE_VERSION => cache,
+            Ok(_) => {
+                log::info!("Build cache is from another version of Saaru, rebuilding everything");
+                BuildCache::default()
+            }
+            Err(e) => {
+                log::warn!("Ignoring unreadable build cache {:?}: {}", path, e);
+                BuildCache::default()
+            }
+        }
+    }
+
+    pub fn save(&self, base_dir: &Path) -> Result<()> {
+        let path = BuildCache::path(base_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SaaruError::io(parent, e))?;
+        }
+        let content = serde_json::to_string(self).expect("build cache always serializes");
+        fs::write(&path, content).map_err(|e| SaaruError::io(&path, e))
+    }
+}
+
+// Every file the last build put in the build directory, so the next one can delete
+// the ones it no longer produces. Kept apart from the cache, since `--no-cache`
+// shouldn't make Saaru forget what it wrote.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BuildManifest {
+    // The build directory these files are in. Nothing gets pruned if it has moved since.
+    pub build_dir: PathBuf,
+    // Build-relative paths
+    pub files: BTreeSet<String>,
+}
+
+impl BuildManifest {
+    pub fn path(base_dir: &Path) -> PathBuf {
+        base_dir.join(CACHE_DIR).join(MANIFEST_FILE)
+    }
+
+    // Without a manifest nothing gets pruned, which is what happened before there was one
+    pub fn load(base_dir: &Path) -> Self {
+        let path = BuildManifest::path(base_dir);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return BuildManifest::default(),
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable build manifest {:?}: {}", path, e);
+            BuildManifest::default()
+        })
+    }
+
+    pub fn save(&self, base_dir: &Path) -> Result<()> {
+        let path = BuildManifest::path(base_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SaaruError::io(parent, e))?;
+        }
+        let content = serde_json::to_string(self).expect("build manifest always serializes");
+        fs::write(&path, content).map_err(|e| SaaruError::io(&path, e))
+    }
+}
+
+// Incrementally hashes every input of an output into a single value
+#[derive(Clone)]
+pub struct Fingerprint(Xxh3);
+
+impl Fingerprint {
+    pub fn new() -> Self {
+        let mut fingerprint = Fingerprint(Xxh3::new());
+        fingerprint.add(env!("CARGO_PKG_VERSION"));
+        fingerprint
+    }
+
+    pub fn add(&mut self, input: impl AsRef<[u8]>) -> &mut Self {
+        let input = input.as_ref();
+        // Length-prefix every input so ("ab", "c") and ("a", "bc") don't collide
+        self.0.update(&(input.len() as u64).to_le_bytes());
+        self.0.update(input);
+        self
+    }
+
+    pub fn finish(&self) -> String {
+        format!("{:016x}", self.0.digest())
+    }
+}
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Fingerprint::new()
+    }
+}
+
+pub fn hash_bytes(input: &[u8]) -> String {
+    Fingerprint::new().add(input).finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inputs_are_kept_apart() {
+        let split = |inputs: &[&str]| {
+            let mut fingerprint = Fingerprint::new();
+            for input in inputs {
+                fingerprint.add(input);
+            }
+            fingerprint.finish()
+        };
+        assert_ne!(split(&["ab", "c"]), split(&["a", "bc"]));
+        assert_ne!(split(&["abc"]), split(&["abc", ""]));
+        assert_eq!(split(&["ab", "c"]), split(&["ab", "c"]));
+    }
+
+    #[test]
+    fn outdated_or_unreadable_caches_start_over() {
+        let base_dir = std::env::temp_dir().join(format!("saaru-cache-{}", std::process::id()));
+        let mut cache = BuildCache::default();
+        cache
+            .outputs
+            .insert("index.html".to_string(), "1234".to_string());
+        cache.save(&base_dir).unwrap();
+        assert_eq!(BuildCache::load(&base_dir).outputs, cache.outputs);
+
+        cache.version = CACHE_VERSION + 1;
+        cache.save(&base_dir).unwrap();
+        let outdated = BuildCache::load(&base_dir);
+
+        fs::write(BuildCache::path(&base_dir), "{ not json").unwrap();
+        let unreadable = BuildCache::load(&base_dir);
+        fs::remove_dir_all(&base_dir).unwrap();
+
+        assert!(outdated.outputs.is_empty());
+        assert_eq!(outdated.version, CACHE_VERSION);
+        assert!(unreadable.outputs.is_empty());
+    }
+}
@@ -0,0 +1,363 @@
+use percent_encoding::percent_decode_str;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::SaaruError;
+use crate::frontmatter::AugmentedFrontMatter;
+
+// Everything a build would write, kept in memory by `saaru check` instead
+#[derive(Debug, Default)]
+pub struct DryRun {
+    // Output path in the build directory -> what was written there, once per write.
+    // Static files are only recorded by path.
+    outputs: HashMap<PathBuf, Vec<Option<String>>>,
+}
+
+impl DryRun {
+    pub fn record(&mut self, output_path: PathBuf, content: Option<String>) {
+        self.outputs.entry(output_path).or_default().push(content);
+    }
+}
+
+/// Something `saaru check` found wrong with a site
+#[derive(Debug)]
+pub enum Problem {
+    /// Anything that fails the build: broken frontmatter, a missing template, a template error
+    Build(SaaruError),
+    /// More than one page or file is written to the same path, so all but one get lost
+    DuplicateOutput {
+        output: PathBuf,
+        sources: Vec<String>,
+    },
+    /// A template looks up a collection no page is in, so it always comes out empty.
+    /// Only a warning, since the pages might just not be written yet.
+    UnknownCollection {
+        template: PathBuf,
+        collection: String,
+    },
+    /// A link to a page or file the site doesn't have
+    BrokenLink { page: PathBuf, link: String },
+}
+
+impl Problem {
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Problem::UnknownCollection { .. })
+    }
+
+    // Heading this problem is grouped under in the report
+    fn category(&self) -> &'static str {
+        match self {
+            Problem::Build(error) => error.category(),
+            Problem::DuplicateOutput { .. } => "Duplicate output paths",
+            Problem::UnknownCollection { .. } => "Unknown collections (warnings)",
+            Problem::BrokenLink { .. } => "Broken internal links",
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Build(error) => write!(f, "{}", error),
+            Problem::DuplicateOutput { output, sources } => write!(
+                f,
+                "{}: written {} times, from {}",
+                output.display(),
+                sources.len(),
+                sources.join(", ")
+            ),
+            Problem::UnknownCollection {
+                template,
+                collection,
+            } => write!(
+                f,
+                "{}: no page is in the collection {:?}",
+                template.display(),
+                collection
+            ),
+            Problem::BrokenLink { page, link } => {
+                write!(f, "{}: nothing to link to at {:?}", page.display(), link)
+            }
+        }
+    }
+}
+
+/// What `saaru check` found
+#[derive(Debug)]
+pub struct CheckReport {
+    pub pages: usize,
+    pub outputs: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl CheckReport {
+    pub fn errors(&self) -> usize {
+        self.problems
+            .iter()
+            .filter(|problem| problem.is_error())
+            .count()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.problems.len() - self.errors()
+    }
+
+    /// Whether the site builds, and builds into something without broken links
+    pub fn is_ok(&self) -> bool {
+        self.errors() == 0
+    }
+}
+
+// Grouped the same way as the report of a --keep-going build
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = self.errors();
+        let warnings = self.warnings();
+        write!(
+            f,
+            "Checked {} pages and {} outputs: {} error{}, {} warning{}",
+            self.pages,
+            self.outputs,
+            errors,
+            if errors == 1 { "" } else { "s" },
+            warnings,
+            if warnings == 1 { "" } else { "s" }
+        )?;
+
+        let mut categories: Vec<&'static str> = Vec::new();
+        for problem in &self.problems {
+            if !categories.contains(&problem.category()) {
+                categories.push(problem.category());
+            }
+        }
+        for category in categories {
+            let grouped: Vec<&Problem> = self
+                .problems
+                .iter()
+                .filter(|problem| problem.category() == category)
+                .collect();
+            write!(f, "\n\n{} ({}):", category, grouped.len())?;
+            for problem in grouped {
+                write!(f, "\n  {}", problem)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Outputs written more than once in a dry run, with what wrote them: source pages,
+// static files, and the tag, collection and other pages Saaru generates
+pub fn duplicate_outputs(
+    dry_run: &DryRun,
+    pages: &HashMap<String, AugmentedFrontMatter>,
+    build_dir: &Path,
+    static_dir: &Path,
+) -> Vec<Problem> {
+    let mut duplicates: Vec<Problem> = dry_run
+        .outputs
+        .iter()
+        .filter(|(_, writes)| writes.len() > 1)
+        .map(|(output, writes)| {
+            let mut sources: Vec<String> = pages
+                .values()
+                .filter(|page| Path::new(&page.write_path) == output)
+                .map(|page| page.source_path.clone())
+                .collect();
+            let relative = output.strip_prefix(build_dir).unwrap_or(output);
+            for content in writes {
+                if content.is_none() {
+                    sources.push(static_dir.join(relative).display().to_string());
+                }
+            }
+            while sources.len() < writes.len() {
+                sources.push("a generated page".to_string());
+            }
+            Problem::DuplicateOutput {
+                output: output.clone(),
+                sources,
+            }
+        })
+        .collect();
+    duplicates.sort_by_key(|duplicate| duplicate.to_string());
+    duplicates
+}
+
+// Links on every page of a dry run that don't lead to anything the build produces.
+// Links resolve the way the live reload server (and most static hosts) resolve them:
+// `/posts/hello` finds `posts/hello.html`, and `/posts/` finds `posts/index.html`.
+pub fn broken_links(
+    dry_run: &DryRun,
+    pages: &HashMap<String, AugmentedFrontMatter>,
+    build_dir: &Path,
+    prefix: &str,
+) -> Vec<Problem> {
+    let outputs: HashSet<&PathBuf> = dry_run.outputs.keys().collect();
+    let exists = |target: &Path| {
+        outputs.contains(&target.to_path_buf())
+            || outputs.contains(&target.join("index.html"))
+            || outputs.contains(&PathBuf::from(format!("{}.html", target.display())))
+    };
+
+    // Problems are reported against the markdown source of a page, where there is one
+    let sources: HashMap<&str, &str> = pages
+        .values()
+        .map(|page| (page.write_path.as_str(), page.source_path.as_str()))
+        .collect();
+
+    let mut broken = BTreeSet::new();
+    for (output, writes) in &dry_run.outputs {
+        if output.extension().and_then(|ext| ext.to_str()) != Some("html") {
+            continue;
+        }
+        let html = match writes.last() {
+            Some(Some(html)) => html,
+            _ => continue,
+        };
+        // The URL path of the page's directory, which relative links start from
+        let directory = output
+            .parent()
+            .and_then(|parent| parent.strip_prefix(build_dir).ok())
+            .map(|parent| parent.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+
+        for link in links(html) {
+            let path = match internal_path(&link) {
+                Some(path) => path,
+                None => continue,
+            };
+            let url = if path.starts_with('/') {
+                strip_site_prefix(&path, prefix).to_string()
+            } else {
+                format!("{}/{}", directory, path)
+            };
+            if !exists(&build_dir.join(resolve_url(&url))) {
+                let page = sources
+                    .get(output.display().to_string().as_str())
+                    .map_or_else(|| output.clone(), PathBuf::from);
+                broken.insert((page, link));
+            }
+        }
+    }
+    broken
+        .into_iter()
+        .map(|(page, link)| Problem::BrokenLink { page, link })
+        .collect()
+}
+
+// Links may or may not include the prefix the site is deployed under, like `/my-project`
+fn strip_site_prefix<'a>(path: &'a str, prefix: &str) -> &'a str {
+    if prefix.is_empty() {
+        return path;
+    }
+    match path.strip_prefix(prefix) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+// Tidy up `.` and `..` in a URL path the way a browser does, where `..` at the root stays there
+fn resolve_url(url: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in url.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+// The values of every quoted `href` and `src` attribute in a page
+fn links(html: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    for attribute in ["href=", "src="] {
+        for (start, _) in html.match_indices(attribute) {
+            // Skip the likes of `data-src=`
+            if !html[..start].ends_with(char::is_whitespace) {
+                continue;
+            }
+            let value = &html[start + attribute.len()..];
+            let quote = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => continue,
+            };
+            if let Some(end) = value[1..].find(quote) {
+                links.push(value[1..end + 1].to_string());
+            }
+        }
+    }
+    links
+}
+
+// The path a link points to inside the site, or `None` if it leads somewhere else
+// (another site, an email address, a spot on the same page)
+fn internal_path(link: &str) -> Option<String> {
+    let link = link.trim();
+    if link.starts_with("//") {
+        return None;
+    }
+    // Anything with a scheme, like `https:`, `mailto:` or `javascript:`
+    if let Some(colon) = link.find(':') {
+        if !link[..colon].contains(['/', '?', '#']) {
+            return None;
+        }
+    }
+    let path = link.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    let path = path.replace("&amp;", "&");
+    Some(percent_decode_str(&path).decode_utf8_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_quoted_links() {
+        let html = r#"<a href="/posts/">Posts</a> <img src='cat.png' data-src="lazy.png"> <a class=x href=bare>"#;
+        assert_eq!(links(html), vec!["/posts/", "cat.png"]);
+    }
+
+    #[test]
+    fn only_internal_links_have_a_path() {
+        assert_eq!(
+            internal_path("/posts/hello"),
+            Some("/posts/hello".to_string())
+        );
+        assert_eq!(
+            internal_path("hello%20world.html#top"),
+            Some("hello world.html".to_string())
+        );
+        assert_eq!(
+            internal_path("search?q=a&amp;b=c"),
+            Some("search".to_string())
+        );
+        assert_eq!(internal_path("./a:b.html"), Some("./a:b.html".to_string()));
+        assert_eq!(internal_path("mailto:someone@example.com"), None);
+        assert_eq!(internal_path("https://example.com/"), None);
+        assert_eq!(internal_path("//cdn.example.com/lib.js"), None);
+        assert_eq!(internal_path("#top"), None);
+    }
+
+    #[test]
+    fn resolves_dots_in_urls() {
+        assert_eq!(resolve_url("posts/./hello"), "posts/hello");
+        assert_eq!(resolve_url("posts/2024/../hello"), "posts/hello");
+        assert_eq!(resolve_url("posts/../../../style.css"), "style.css");
+        assert_eq!(resolve_url("/"), "");
+    }
+
+    #[test]
+    fn strips_the_site_prefix() {
+        assert_eq!(strip_site_prefix("/blog/posts/", "/blog"), "/posts/");
+        assert_eq!(strip_site_prefix("/blog", "/blog"), "");
+        assert_eq!(strip_site_prefix("/blogroll", "/blog"), "/blogroll");
+        assert_eq!(strip_site_prefix("/posts/", ""), "/posts/");
+    }
+}
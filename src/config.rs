@@ -0,0 +1,512 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{Result, SaaruError};
+use crate::feed::FeedConfig;
+use crate::listing::{CollectionConfig, TagConfig};
+use crate::sitemap::SitemapConfig;
+use crate::utils::is_contained;
+
+pub const DEFAULT_TEMPLATE: &str = "post.jinja";
+pub const DEFAULT_NOT_FOUND_TEMPLATE: &str = "404.jinja";
+pub const DEFAULT_RENDER_THREADS: usize = 10;
+// Files in the build directory that Saaru never deletes, unless `preserve` says otherwise
+pub const DEFAULT_PRESERVED: [&str; 3] = ["CNAME", ".nojekyll", ".git"];
+
+// The config files Saaru looks for in the base path, in order of precedence.
+// Only one of them may exist at a time.
+pub const CONFIG_FILE_NAMES: [&str; 4] = [".saaru.json", "saaru.toml", "saaru.yaml", "saaru.yml"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    // Work out the format from the file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ConfigFormat::Json),
+            "toml" => Some(ConfigFormat::Toml),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+// The site configuration, as read from `.saaru.json`, `saaru.toml` or `saaru.yaml`.
+// Every section is optional and falls back to the defaults below.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SaaruConfig {
+    pub metadata: SiteMetadata,
+    pub directories: DirectoryConfig,
+    pub markdown: MarkdownConfig,
+    // Number of threads rendering pages in parallel
+    pub threads: usize,
+    pub server: ServerConfig,
+    pub collections: HashMap<String, CollectionConfig>,
+    pub tags: TagConfig,
+    pub feeds: Option<FeedConfig>,
+    pub sitemap: SitemapConfig,
+    // Paths inside the build directory (and everything under them) that are never
+    // deleted, neither by `--clean` nor when pruning outputs the build no longer produces
+    pub preserve: Vec<PathBuf>,
+}
+
+impl Default for SaaruConfig {
+    fn default() -> Self {
+        SaaruConfig {
+            metadata: SiteMetadata::default(),
+            directories: DirectoryConfig::default(),
+            markdown: MarkdownConfig::default(),
+            threads: DEFAULT_RENDER_THREADS,
+            server: ServerConfig::default(),
+            collections: HashMap::new(),
+            tags: TagConfig::default(),
+            feeds: None,
+            sitemap: SitemapConfig::default(),
+            preserve: DEFAULT_PRESERVED.iter().map(PathBuf::from).collect(),
+        }
+    }
+}
+
+// `metadata` is free-form, since templates read whatever they like from `base.json.metadata`.
+// These are just the parts Saaru itself understands.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SiteMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    // Absolute URL the site is deployed at, needed for feeds and the sitemap
+    pub base_url: Option<String>,
+    pub author: AuthorMetadata,
+    pub templates: TemplateConfig,
+}
+
+impl SiteMetadata {
+    // The base URL without a trailing slash
+    pub fn base_url(&self) -> Option<String> {
+        self.base_url
+            .as_ref()
+            .map(|url| url.trim_end_matches('/').to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AuthorMetadata {
+    pub name: Option<String>,
+    pub one_line_desc: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TemplateConfig {
+    // Used for every page that doesn't set `template` in its frontmatter
+    pub default: String,
+    // Renders `404.html`. Without it, `404.jinja` is used if the site has one.
+    pub not_found: Option<String>,
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        TemplateConfig {
+            default: DEFAULT_TEMPLATE.to_string(),
+            not_found: None,
+        }
+    }
+}
+
+impl TemplateConfig {
+    pub fn not_found(&self) -> &str {
+        self.not_found
+            .as_deref()
+            .unwrap_or(DEFAULT_NOT_FOUND_TEMPLATE)
+    }
+}
+
+// Where everything lives, relative to the base path
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DirectoryConfig {
+    pub source: PathBuf,
+    pub templates: PathBuf,
+    #[serde(rename = "static")]
+    pub static_files: PathBuf,
+    pub build: PathBuf,
+}
+
+impl Default for DirectoryConfig {
+    fn default() -> Self {
+        DirectoryConfig {
+            source: PathBuf::from("src"),
+            templates: PathBuf::from("templates"),
+            static_files: PathBuf::from("static"),
+            build: PathBuf::from("build"),
+        }
+    }
+}
+
+// Markdown extensions, all of these map onto comrak's options
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MarkdownConfig {
+    // Let raw HTML in the markdown through to the page
+    pub unsafe_html: bool,
+    pub tables: bool,
+    pub autolink: bool,
+    pub tasklists: bool,
+    pub footnotes: bool,
+    pub strikethrough: bool,
+    pub description_lists: bool,
+    pub superscript: bool,
+    pub tagfilter: bool,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        MarkdownConfig {
+            unsafe_html: true,
+            tables: true,
+            autolink: true,
+            tasklists: true,
+            footnotes: true,
+            strikethrough: true,
+            description_lists: true,
+            superscript: false,
+            tagfilter: false,
+        }
+    }
+}
+
+// Where the live-reload web server listens
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    #[serde(alias = "host")]
+    pub address: String,
+    // The first port to try, the next free one is used if it's taken
+    pub port: u16,
+    // Serve the site under this path, like `/my-project` for a GitHub Pages project site.
+    // Defaults to the path of `metadata.base_url`.
+    pub prefix: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: "127.0.0.1".to_string(),
+            port: 3000,
+            prefix: None,
+        }
+    }
+}
+
+impl SaaruConfig {
+    // Find the config file in the base path, if there is one
+    pub fn discover(base_dir: &Path) -> Result<Option<PathBuf>> {
+        let found: Vec<PathBuf> = CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| base_dir.join(name))
+            .filter(|path| path.is_file())
+            .collect();
+        match found.as_slice() {
+            [] => Ok(None),
+            [path] => Ok(Some(path.clone())),
+            [first, ..] => Err(SaaruError::config(
+                first,
+                format!(
+                    "found more than one config file ({}), keep only one of them or pick one with --config",
+                    found
+                        .iter()
+                        .filter_map(|path| path.file_name())
+                        .map(|name| name.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )),
+        }
+    }
+
+    // The path the web server serves the site under: empty for the root, `/something` otherwise
+    pub fn server_prefix(&self) -> String {
+        let prefix = match &self.server.prefix {
+            Some(prefix) => prefix.clone(),
+            // `https://user.github.io/project` -> `project`
+            None => self
+                .metadata
+                .base_url()
+                .and_then(|url| url.splitn(4, '/').nth(3).map(str::to_string))
+                .unwrap_or_default(),
+        };
+        let prefix = prefix.trim_matches('/');
+        if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", prefix)
+        }
+    }
+
+    // Read, parse and validate a config file in any of the supported formats
+    pub fn from_file(config_path: &Path) -> Result<(SaaruConfig, Value)> {
+        let format = ConfigFormat::from_path(config_path).ok_or_else(|| {
+            SaaruError::config(
+                config_path,
+                "unsupported config format, use a .json, .toml or .yaml file",
+            )
+        })?;
+        let content = read_to_string(config_path).map_err(|e| SaaruError::io(config_path, e))?;
+        SaaruConfig::from_str(&content, format, config_path)
+    }
+
+    // Parse and validate a config, warning about (but otherwise ignoring) keys Saaru
+    // doesn't know about. Every format is turned into JSON first, which is also what
+    // templates get to see as `base.json`.
+    pub fn from_str(
+        content: &str,
+        format: ConfigFormat,
+        config_path: &Path,
+    ) -> Result<(SaaruConfig, Value)> {
+        let raw: Value = match format {
+            ConfigFormat::Json => {
+                serde_json::from_str(content).map_err(|e| format!("invalid JSON: {}", e))
+            }
+            ConfigFormat::Toml => content
+                .parse::<toml::Table>()
+                .map(|table| toml_to_json(toml::Value::Table(table)))
+                .map_err(|e| format!("invalid TOML: {}", e)),
+            ConfigFormat::Yaml => {
+                serde_yaml::from_str(content).map_err(|e| format!("invalid YAML: {}", e))
+            }
+        }
+        .map_err(|e| SaaruError::config(config_path, e))?;
+
+        // An empty YAML file is null rather than an empty mapping
+        let raw = match raw {
+            Value::Null => Value::Object(Default::default()),
+            raw => raw,
+        };
+
+        let config = deserialize_with_warnings(&raw, config_path)
+            .map_err(|e| SaaruError::config(config_path, e))?;
+        config.validate(config_path)?;
+        Ok((config, raw))
+    }
+
+    fn validate(&self, config_path: &Path) -> Result<()> {
+        let invalid = |message: String| Err(SaaruError::config(config_path, message));
+
+        if self.threads == 0 {
+            return invalid("threads must be at least 1".to_string());
+        }
+        if self.metadata.templates.default.trim().is_empty() {
+            return invalid("metadata.templates.default can't be empty".to_string());
+        }
+        if let Some(base_url) = &self.metadata.base_url {
+            if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+                return invalid(format!(
+                    "metadata.base_url must be an absolute http(s) URL, found {:?}",
+                    base_url
+                ));
+            }
+        }
+        if let Some(feeds) = &self.feeds {
+            if self.metadata.base_url.is_none() {
+                return invalid(
+                    "metadata.base_url is needed to generate feeds, since feed links must be absolute"
+                        .to_string(),
+                );
+            }
+            if feeds.limit == 0 {
+                return invalid("feeds.limit must be at least 1".to_string());
+            }
+        }
+        if self.server.address.trim().is_empty() {
+            return invalid("server.address can't be empty".to_string());
+        }
+        for (name, directory) in [
+            ("source", &self.directories.source),
+            ("templates", &self.directories.templates),
+            ("static", &self.directories.static_files),
+            ("build", &self.directories.build),
+        ] {
+            if directory.as_os_str().is_empty() {
+                return invalid(format!("directories.{} can't be empty", name));
+            }
+        }
+        for (name, collection) in &self.collections {
+            let path = collection.path(name);
+            if !is_contained(Path::new(&path)) {
+                return invalid(format!(
+                    "collections.{}.path must be a path inside the build directory, found {:?}",
+                    name, path
+                ));
+            }
+        }
+        for path in &self.preserve {
+            if path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+                return invalid(format!(
+                    "preserve entries must be relative paths inside the build directory, found {:?}",
+                    path
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// TOML has a datetime type that JSON doesn't, so those become strings
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(integer) => Value::from(integer),
+        toml::Value::Float(float) => Value::from(float),
+        toml::Value::Boolean(boolean) => Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => Value::Array(array.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+// Deserialize the config, logging a warning for every key that isn't used, and
+// naming the offending key (`collections.posts.page_size`) when a value is invalid
+fn deserialize_with_warnings<'de, D>(
+    deserializer: D,
+    config_path: &Path,
+) -> std::result::Result<SaaruConfig, String>
+where
+    D: serde::Deserializer<'de>,
+    D::Error: std::fmt::Display,
+{
+    let mut warn = |path: serde_ignored::Path| {
+        // Anything goes under `metadata`, templates are free to read it
+        let key = path.to_string();
+        if !key.starts_with("metadata.") {
+            log::warn!("{:?}: unknown key `{}` will be ignored", config_path, key);
+        }
+    };
+    serde_path_to_error::deserialize(serde_ignored::Deserializer::new(deserializer, &mut warn))
+        .map_err(|e| {
+            let key = e.path().to_string();
+            if key == "." {
+                e.into_inner().to_string()
+            } else {
+                format!("invalid value for `{}`: {}", key, e.into_inner())
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn parse(content: &str, format: ConfigFormat) -> Result<(SaaruConfig, Value)> {
+        SaaruConfig::from_str(content, format, Path::new("saaru.test"))
+    }
+
+    #[test]
+    fn more_than_one_config_file_is_an_error() {
+        let base_dir = std::env::temp_dir().join(format!("saaru-config-{}", std::process::id()));
+        fs::create_dir_all(&base_dir).unwrap();
+        fs::write(base_dir.join(".saaru.json"), "{}").unwrap();
+        assert_eq!(
+            SaaruConfig::discover(&base_dir).unwrap(),
+            Some(base_dir.join(".saaru.json"))
+        );
+
+        fs::write(base_dir.join("saaru.toml"), "").unwrap();
+        let error = SaaruConfig::discover(&base_dir).unwrap_err().to_string();
+        fs::remove_dir_all(&base_dir).unwrap();
+        assert!(error.contains(".saaru.json, saaru.toml"), "{}", error);
+    }
+
+    #[test]
+    fn detects_the_format_from_the_extension() {
+        let format = |name: &str| ConfigFormat::from_path(Path::new(name));
+        assert_eq!(format(".saaru.json"), Some(ConfigFormat::Json));
+        assert_eq!(format("saaru.toml"), Some(ConfigFormat::Toml));
+        assert_eq!(format("saaru.yaml"), Some(ConfigFormat::Yaml));
+        assert_eq!(format("saaru.yml"), Some(ConfigFormat::Yaml));
+        assert_eq!(format("saaru.ini"), None);
+        assert_eq!(format("saaru"), None);
+    }
+
+    #[test]
+    fn every_format_reads_the_same() {
+        let json = r#"{"metadata": {"title": "Site"}, "threads": 2}"#;
+        let toml = "threads = 2\n[metadata]\ntitle = \"Site\"\n";
+        let yaml = "metadata:\n  title: Site\nthreads: 2\n";
+        for (content, format) in [
+            (json, ConfigFormat::Json),
+            (toml, ConfigFormat::Toml),
+            (yaml, ConfigFormat::Yaml),
+        ] {
+            let (config, raw) = parse(content, format).unwrap();
+            assert_eq!(config.metadata.title.as_deref(), Some("Site"));
+            assert_eq!(config.threads, 2);
+            assert_eq!(raw["metadata"]["title"], "Site");
+        }
+    }
+
+    #[test]
+    fn empty_yaml_is_an_empty_mapping() {
+        let (config, raw) = parse("", ConfigFormat::Yaml).unwrap();
+        assert_eq!(raw, Value::Object(Default::default()));
+        assert_eq!(config.threads, DEFAULT_RENDER_THREADS);
+    }
+
+    #[test]
+    fn toml_datetimes_become_strings() {
+        let (_, raw) = parse(
+            "[metadata]\nupdated = 2023-01-05T10:30:00Z\nlaunch = 2023-01-05\n",
+            ConfigFormat::Toml,
+        )
+        .unwrap();
+        assert_eq!(raw["metadata"]["updated"], "2023-01-05T10:30:00Z");
+        assert_eq!(raw["metadata"]["launch"], "2023-01-05");
+    }
+
+    #[test]
+    fn unknown_keys_are_only_warned_about() {
+        let (config, raw) = parse(
+            r#"{"colections": {}, "markdown": {"tabels": false}, "metadata": {"anything": 1}}"#,
+            ConfigFormat::Json,
+        )
+        .unwrap();
+        assert!(config.markdown.tables);
+        assert_eq!(raw["metadata"]["anything"], 1);
+    }
+
+    #[test]
+    fn invalid_values_name_the_key() {
+        let error = parse(r#"{"threads": "many"}"#, ConfigFormat::Json)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("invalid value for `threads`"), "{}", error);
+    }
+
+    #[test]
+    fn collection_paths_stay_inside_the_build_directory() {
+        assert!(parse(
+            r#"{"collections": {"posts": {"path": "/writing/"}}}"#,
+            ConfigFormat::Json
+        )
+        .is_ok());
+        for path in ["../escaped", "/posts/../../escaped"] {
+            let content = format!(r#"{{"collections": {{"posts": {{"path": "{}"}}}}}}"#, path);
+            assert!(parse(&content, ConfigFormat::Json).is_err(), "{}", path);
+        }
+    }
+}
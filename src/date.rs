@@ -0,0 +1,146 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+
+// Formats accepted for a date with a time but no timezone (read as UTC)
+const DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"];
+
+// Formats accepted for a plain date (read as midnight UTC)
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%d %B %Y", "%B %d, %Y"];
+
+const ACCEPTED_FORMATS: &str = "RFC 3339 (2023-01-05T10:30:00+05:30), RFC 2822, \
+    YYYY-MM-DD HH:MM[:SS], YYYY-MM-DD, YYYY/MM/DD, \"5 January 2023\" or \"January 5, 2023\"";
+
+/// The `date` field of the frontmatter.
+/// Parsed up front so pages can be sorted chronologically, but it's handed back to
+/// templates exactly as the author wrote it.
+#[derive(Clone, Debug)]
+pub struct PostDate {
+    pub datetime: DateTime<FixedOffset>,
+    raw: String,
+}
+
+impl PostDate {
+    pub fn parse(raw: &str) -> Result<PostDate, String> {
+        let trimmed = raw.trim();
+        let utc = FixedOffset::east_opt(0).expect("zero offset is always valid");
+
+        let datetime = DateTime::parse_from_rfc3339(trimmed)
+            .or_else(|_| DateTime::parse_from_rfc2822(trimmed))
+            .ok()
+            .or_else(|| {
+                DATETIME_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(trimmed, format).ok())
+                    .or_else(|| {
+                        DATE_FORMATS
+                            .iter()
+                            .find_map(|format| NaiveDate::parse_from_str(trimmed, format).ok())
+                            .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    })
+                    .map(|naive| utc.from_utc_datetime(&naive))
+            })
+            .ok_or_else(|| {
+                format!(
+                    "invalid date {:?}, expected one of: {}",
+                    trimmed, ACCEPTED_FORMATS
+                )
+            })?;
+
+        Ok(PostDate {
+            datetime,
+            raw: trimmed.to_string(),
+        })
+    }
+
+    /// Formatted for an RSS `<pubDate>`
+    pub fn to_rfc2822(&self) -> String {
+        self.datetime.to_rfc2822()
+    }
+
+    /// Formatted for Atom's `<updated>` and `<published>`
+    pub fn to_rfc3339(&self) -> String {
+        self.datetime.to_rfc3339()
+    }
+}
+
+impl fmt::Display for PostDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+impl PartialEq for PostDate {
+    fn eq(&self, other: &Self) -> bool {
+        self.datetime == other.datetime
+    }
+}
+
+impl Eq for PostDate {}
+
+impl PartialOrd for PostDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PostDate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.datetime.cmp(&other.datetime)
+    }
+}
+
+impl Serialize for PostDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for PostDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        PostDate::parse(&raw).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc3339(raw: &str) -> String {
+        PostDate::parse(raw).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn parses_every_accepted_format() {
+        assert_eq!(
+            rfc3339("2023-01-05T10:30:00+05:30"),
+            "2023-01-05T10:30:00+05:30"
+        );
+        assert_eq!(
+            rfc3339("Thu, 05 Jan 2023 10:30:00 +0530"),
+            "2023-01-05T10:30:00+05:30"
+        );
+        assert_eq!(rfc3339("2023-01-05 10:30:15"), "2023-01-05T10:30:15+00:00");
+        assert_eq!(rfc3339("2023-01-05T10:30:15"), "2023-01-05T10:30:15+00:00");
+        assert_eq!(rfc3339("2023-01-05 10:30"), "2023-01-05T10:30:00+00:00");
+        assert_eq!(rfc3339("2023-01-05"), "2023-01-05T00:00:00+00:00");
+        assert_eq!(rfc3339("2023/01/05"), "2023-01-05T00:00:00+00:00");
+        assert_eq!(rfc3339("5 January 2023"), "2023-01-05T00:00:00+00:00");
+        assert_eq!(rfc3339("January 5, 2023"), "2023-01-05T00:00:00+00:00");
+    }
+
+    #[test]
+    fn keeps_the_date_as_written() {
+        let date = PostDate::parse("  5 January 2023 ").unwrap();
+        assert_eq!(date.to_string(), "5 January 2023");
+        assert_eq!(date, PostDate::parse("2023-01-05").unwrap());
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        let error = PostDate::parse("05.01.2023").unwrap_err();
+        assert!(error.contains("invalid date \"05.01.2023\""), "{}", error);
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type Result<T> = std::result::Result<T, SaaruError>;
+
+/// Everything that can go wrong while building a site. Every variant carries the
+/// path of the file being worked on, so the CLI can tell you *where* it broke.
+#[derive(Debug)]
+pub enum SaaruError {
+    /// Reading or writing a file / directory failed
+    Io { path: PathBuf, source: io::Error },
+    /// The YAML frontmatter of a source file is missing or malformed
+    FrontMatter {
+        path: PathBuf,
+        line: Option<usize>,
+        message: String,
+    },
+    /// A page asked for a template that isn't in the template directory
+    TemplateNotFound { path: PathBuf, template: String },
+    /// The template exists, but minijinja failed to load or render it
+    Template {
+        path: PathBuf,
+        template: String,
+        source: minijinja::Error,
+    },
+    /// The site configuration (or directory layout) is invalid
+    Config { path: PathBuf, message: String },
+    /// The filesystem watcher couldn't be set up
+    Watch {
+        path: PathBuf,
+        source: notify::Error,
+    },
+    /// A --keep-going build that collected one or more of the errors above
+    Build { errors: Vec<SaaruError> },
+}
+
+impl SaaruError {
+    pub fn io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        SaaruError::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn config(path: impl AsRef<Path>, message: impl Into<String>) -> Self {
+        SaaruError::Config {
+            path: path.as_ref().to_path_buf(),
+            message: message.into(),
+        }
+    }
+
+    pub fn template(path: impl AsRef<Path>, template: &str, source: minijinja::Error) -> Self {
+        // Lookups fail with a minijinja error too, but "template not found" deserves
+        // its own message since it's nearly always a typo in the frontmatter
+        if source.kind() == minijinja::ErrorKind::TemplateNotFound {
+            SaaruError::TemplateNotFound {
+                path: path.as_ref().to_path_buf(),
+                template: template.to_string(),
+            }
+        } else {
+            SaaruError::Template {
+                path: path.as_ref().to_path_buf(),
+                template: template.to_string(),
+                source,
+            }
+        }
+    }
+
+    // Heading this error is grouped under in the end-of-build report
+    pub(crate) fn category(&self) -> &'static str {
+        match self {
+            SaaruError::FrontMatter { .. } => "Frontmatter errors",
+            SaaruError::TemplateNotFound { .. } => "Missing templates",
+            SaaruError::Template { .. } => "Template errors",
+            SaaruError::Io { .. } => "Read/write failures",
+            SaaruError::Config { .. } => "Configuration errors",
+            SaaruError::Watch { .. } => "Watcher errors",
+            SaaruError::Build { .. } => "Build errors",
+        }
+    }
+}
+
+// Write every collected error, grouped by kind, so an author can fix them all in one go
+fn write_report(f: &mut fmt::Formatter<'_>, errors: &[SaaruError]) -> fmt::Result {
+    let mut categories: Vec<&'static str> = Vec::new();
+    for error in errors {
+        if !categories.contains(&error.category()) {
+            categories.push(error.category());
+        }
+    }
+
+    let plural = if errors.len() == 1 { "" } else { "s" };
+    write!(f, "build failed with {} error{}", errors.len(), plural)?;
+    for category in categories {
+        let grouped: Vec<&SaaruError> = errors
+            .iter()
+            .filter(|error| error.category() == category)
+            .collect();
+        write!(f, "\n\n{} ({}):", category, grouped.len())?;
+        for error in grouped {
+            write!(f, "\n  {}", error)?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for SaaruError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaaruError::Io { path, source } => {
+                write!(f, "{}: I/O error: {}", path.display(), source)
+            }
+            SaaruError::FrontMatter {
+                path,
+                line: Some(line),
+                message,
+            } => write!(
+                f,
+                "{}:{}: invalid frontmatter: {}",
+                path.display(),
+                line,
+                message
+            ),
+            SaaruError::FrontMatter {
+                path,
+                line: None,
+                message,
+            } => write!(f, "{}: invalid frontmatter: {}", path.display(), message),
+            SaaruError::TemplateNotFound { path, template } => write!(
+                f,
+                "{}: template {:?} not found in the template directory",
+                path.display(),
+                template
+            ),
+            // minijinja already appends "(in template:line)" to its own messages
+            SaaruError::Template {
+                path,
+                template,
+                source,
+            } => write!(
+                f,
+                "{}: error in template {:?}: {}",
+                path.display(),
+                template,
+                source
+            ),
+            SaaruError::Config { path, message } => {
+                write!(f, "{}: configuration error: {}", path.display(), message)
+            }
+            SaaruError::Watch { path, source } => {
+                write!(
+                    f,
+                    "{}: could not watch for changes: {}",
+                    path.display(),
+                    source
+                )
+            }
+            SaaruError::Build { errors } => write_report(f, errors),
+        }
+    }
+}
+
+impl std::error::Error for SaaruError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaaruError::Io { source, .. } => Some(source),
+            SaaruError::Template { source, .. } => Some(source),
+            SaaruError::Watch { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
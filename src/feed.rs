@@ -0,0 +1,255 @@
+use serde::{Deserialize, Deserializer};
+use std::path::Path;
+
+use crate::config::SiteMetadata;
+use crate::date::PostDate;
+use crate::error::{Result, SaaruError};
+
+pub const DEFAULT_FEED_LIMIT: usize = 20;
+
+// The `feeds` section of the site config. Feeds are only generated when it's present.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct FeedConfig {
+    // File names for each format, `false` turns that format off
+    #[serde(deserialize_with = "feed_file_name")]
+    pub rss: Option<String>,
+    #[serde(deserialize_with = "feed_file_name")]
+    pub atom: Option<String>,
+    // Collections that get their own feed next to their index pages
+    pub collections: Vec<String>,
+    // Whether every tag gets its own feed under /tags/<tag>/
+    pub tags: bool,
+    // Maximum number of entries in a single feed
+    pub limit: usize,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        FeedConfig {
+            rss: Some("rss.xml".to_string()),
+            atom: Some("atom.xml".to_string()),
+            collections: Vec::new(),
+            tags: false,
+            limit: DEFAULT_FEED_LIMIT,
+        }
+    }
+}
+
+// A feed file is either a file name, or `false` to skip that format
+fn feed_file_name<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FeedFile {
+        Name(String),
+        Enabled(bool),
+    }
+    match Option::<FeedFile>::deserialize(deserializer)? {
+        Some(FeedFile::Name(name)) => Ok(Some(name.trim_matches('/').to_string())),
+        Some(FeedFile::Enabled(false)) | None => Ok(None),
+        Some(FeedFile::Enabled(true)) => Err(serde::de::Error::custom(
+            "expected a file name, or false to turn this feed off",
+        )),
+    }
+}
+
+// Site-wide details every feed needs, taken from `metadata` in the site config
+#[derive(Debug, Clone)]
+pub struct FeedChannel {
+    pub title: String,
+    pub description: String,
+    pub author: String,
+    // Absolute URL of the site, without a trailing slash
+    pub base_url: String,
+}
+
+impl FeedChannel {
+    pub fn from_metadata(metadata: &SiteMetadata, config_path: &Path) -> Result<Self> {
+        let base_url = metadata.base_url().ok_or_else(|| {
+            SaaruError::config(
+                config_path,
+                "metadata.base_url is needed to generate feeds, since feed links must be absolute",
+            )
+        })?;
+        let author = metadata.author.name.clone().unwrap_or_default();
+        let title = metadata.title.clone().unwrap_or_else(|| author.clone());
+        let description = metadata
+            .description
+            .clone()
+            .or_else(|| metadata.author.one_line_desc.clone())
+            .unwrap_or_default();
+
+        Ok(FeedChannel {
+            title,
+            description,
+            author,
+            base_url,
+        })
+    }
+
+    pub fn absolute(&self, link: &str) -> String {
+        format!("{}/{}", self.base_url, link.trim_start_matches('/'))
+    }
+}
+
+// A single post in a feed
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub title: String,
+    pub description: String,
+    // Absolute URL of the rendered page
+    pub link: String,
+    pub date: PostDate,
+    // The post's markdown rendered to HTML, without any template around it
+    pub content: String,
+}
+
+pub fn render_rss(
+    channel: &FeedChannel,
+    title: &str,
+    self_link: &str,
+    entries: &[FeedEntry],
+) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+    xml.push_str("<channel>\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(title)));
+    xml.push_str(&format!(
+        "  <link>{}/</link>\n",
+        escape_xml(&channel.base_url)
+    ));
+    xml.push_str(&format!(
+        "  <description>{}</description>\n",
+        escape_xml(&channel.description)
+    ));
+    xml.push_str(&format!(
+        "  <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        escape_xml(self_link)
+    ));
+    if let Some(latest) = entries.first() {
+        xml.push_str(&format!(
+            "  <lastBuildDate>{}</lastBuildDate>\n",
+            latest.date.to_rfc2822()
+        ));
+    }
+    for entry in entries {
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape_xml(&entry.title)
+        ));
+        xml.push_str(&format!("    <link>{}</link>\n", escape_xml(&entry.link)));
+        xml.push_str(&format!(
+            "    <guid isPermaLink=\"true\">{}</guid>\n",
+            escape_xml(&entry.link)
+        ));
+        xml.push_str(&format!(
+            "    <pubDate>{}</pubDate>\n",
+            entry.date.to_rfc2822()
+        ));
+        // RSS's own <author> wants an email address, so use Dublin Core for the name
+        if !channel.author.is_empty() {
+            xml.push_str(&format!(
+                "    <dc:creator>{}</dc:creator>\n",
+                escape_xml(&channel.author)
+            ));
+        }
+        xml.push_str(&format!(
+            "    <description>{}</description>\n",
+            escape_xml(&entry.description)
+        ));
+        xml.push_str(&format!(
+            "    <content:encoded>{}</content:encoded>\n",
+            escape_xml(&entry.content)
+        ));
+        xml.push_str("  </item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+pub fn render_atom(
+    channel: &FeedChannel,
+    title: &str,
+    self_link: &str,
+    entries: &[FeedEntry],
+) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(title)));
+    if !channel.description.is_empty() {
+        xml.push_str(&format!(
+            "  <subtitle>{}</subtitle>\n",
+            escape_xml(&channel.description)
+        ));
+    }
+    xml.push_str(&format!("  <id>{}</id>\n", escape_xml(self_link)));
+    xml.push_str(&format!(
+        "  <link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n",
+        escape_xml(self_link)
+    ));
+    xml.push_str(&format!(
+        "  <link href=\"{}/\" rel=\"alternate\" type=\"text/html\"/>\n",
+        escape_xml(&channel.base_url)
+    ));
+    // Atom insists on an <updated>, even for an empty feed
+    let updated = entries
+        .first()
+        .map(|entry| entry.date.to_rfc3339())
+        .unwrap_or_else(|| "1970-01-01T00:00:00+00:00".to_string());
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated));
+    xml.push_str(&format!(
+        "  <author><name>{}</name></author>\n",
+        escape_xml(&channel.author)
+    ));
+    for entry in entries {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape_xml(&entry.title)
+        ));
+        xml.push_str(&format!("    <id>{}</id>\n", escape_xml(&entry.link)));
+        xml.push_str(&format!(
+            "    <link href=\"{}\" rel=\"alternate\" type=\"text/html\"/>\n",
+            escape_xml(&entry.link)
+        ));
+        xml.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            entry.date.to_rfc3339()
+        ));
+        xml.push_str(&format!(
+            "    <published>{}</published>\n",
+            entry.date.to_rfc3339()
+        ));
+        if !entry.description.is_empty() {
+            xml.push_str(&format!(
+                "    <summary>{}</summary>\n",
+                escape_xml(&entry.description)
+            ));
+        }
+        xml.push_str(&format!(
+            "    <content type=\"html\">{}</content>\n",
+            escape_xml(&entry.content)
+        ));
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+pub fn escape_xml(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for character in input.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
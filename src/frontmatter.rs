@@ -1,17 +1,25 @@
+use gray_matter::{engine::YAML, Matter, Pod};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use yaml_rust::YamlLoader;
+
+use crate::date::PostDate;
+use crate::error::{Result, SaaruError};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct FrontMatter {
     pub title: Option<String>,
     pub description: Option<String>,
-    pub date: Option<String>,
+    pub date: Option<PostDate>,
     pub tags: Option<Vec<String>>,
     pub collections: Option<Vec<String>>,
     pub wip: Option<bool>,
     pub template: Option<String>,
     pub link: Option<String>,
     pub meta: Option<HashMap<String, String>>,
+    // `sitemap: false` keeps a page out of sitemap.xml
+    pub sitemap: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -29,11 +37,14 @@ pub struct ThinAugmentedFrontMatter {
     pub source_path: String,
     pub write_path: String,
     pub link: String,
+    // Only ever true when building with --drafts
+    pub is_draft: bool,
 }
 
 impl From<AugmentedFrontMatter> for ThinAugmentedFrontMatter {
     fn from(old: AugmentedFrontMatter) -> Self {
         ThinAugmentedFrontMatter {
+            is_draft: old.frontmatter.is_draft(),
             frontmatter: old.frontmatter,
             source_path: old.source_path,
             write_path: old.write_path,
@@ -41,3 +52,60 @@ impl From<AugmentedFrontMatter> for ThinAugmentedFrontMatter {
         }
     }
 }
+
+impl FrontMatter {
+    // Pages marked `wip: true` are drafts, and are left out of the build unless asked for
+    pub fn is_draft(&self) -> bool {
+        self.wip.unwrap_or(false)
+    }
+
+    // Parse the YAML frontmatter at the top of a markdown file.
+    // gray_matter swallows YAML syntax errors (you just get a null back), so when that
+    // happens we re-run the YAML scanner ourselves to recover the line and cause.
+    pub fn from_markdown(
+        parser: &Matter<YAML>,
+        source_path: &Path,
+        markdown_file_content: &str,
+    ) -> Result<FrontMatter> {
+        let parsed = parser.parse(markdown_file_content);
+
+        let data = match parsed.data {
+            Some(data) => data,
+            None => {
+                return Err(SaaruError::FrontMatter {
+                    path: source_path.to_path_buf(),
+                    line: Some(1),
+                    message: "no frontmatter block found (expected the file to start with ---)"
+                        .to_string(),
+                })
+            }
+        };
+
+        if let Pod::Null = data {
+            if let Err(e) = YamlLoader::load_from_str(&parsed.matter) {
+                // The matter block starts somewhere after the opening delimiter, so offset
+                // the scanner's line number to point into the original file
+                let offset = markdown_file_content
+                    .find(&parsed.matter)
+                    .map(|index| markdown_file_content[..index].lines().count())
+                    .unwrap_or(1);
+                let message = e.to_string();
+                let message = match message.rsplit_once(" at line ") {
+                    Some((cause, _)) => cause.to_string(),
+                    None => message,
+                };
+                return Err(SaaruError::FrontMatter {
+                    path: source_path.to_path_buf(),
+                    line: Some(offset + e.marker().line()),
+                    message,
+                });
+            }
+        }
+
+        data.deserialize().map_err(|e| SaaruError::FrontMatter {
+            path: source_path.to_path_buf(),
+            line: None,
+            message: e.to_string(),
+        })
+    }
+}
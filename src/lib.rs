@@ -0,0 +1,35 @@
+//! Saaru, a static site generator. Everything the `saaru` command does is available
+//! from here too:
+//!
+//! ```no_run
+//! let report = saaru::Site::builder().base_path("docs").build()?.render()?;
+//! println!("{}", report);
+//! # Ok::<(), saaru::SaaruError>(())
+//! ```
+//!
+//! The frontmatter types pages are read into are here too, for tools that work with
+//! a site's markdown files directly.
+
+mod arguments;
+mod cache;
+mod check;
+mod config;
+mod date;
+mod error;
+mod feed;
+mod frontmatter;
+mod listing;
+mod overlay;
+mod saaru;
+mod scaffold;
+mod site;
+mod sitemap;
+mod templates;
+mod utils;
+
+pub use check::{CheckReport, Problem};
+pub use date::PostDate;
+pub use error::{Result, SaaruError};
+pub use frontmatter::{AugmentedFrontMatter, FrontMatter, ThinAugmentedFrontMatter};
+pub use scaffold::{new_site, NewPost};
+pub use site::{BuildReport, Site, SiteBuilder};
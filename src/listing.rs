@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::PathBuf;
+
+use crate::frontmatter::ThinAugmentedFrontMatter;
+
+// What to order a tag or collection listing by
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Date,
+    Title,
+    Path,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum SortOrder {
+    #[serde(rename = "asc")]
+    Ascending,
+    #[serde(rename = "desc")]
+    Descending,
+}
+
+// How a single listing (`base.tags[...]` or `base.collections[...]`) is ordered.
+// Newest-first unless the site config says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListingSort {
+    pub key: SortKey,
+    pub order: SortOrder,
+}
+
+impl Default for ListingSort {
+    fn default() -> Self {
+        ListingSort {
+            key: SortKey::Date,
+            order: SortOrder::Descending,
+        }
+    }
+}
+
+impl ListingSort {
+    pub fn sort(&self, listing: &mut [ThinAugmentedFrontMatter]) {
+        listing.sort_by(|a, b| {
+            let ordering = match self.key {
+                SortKey::Date => {
+                    // Undated pages always go to the end of the listing
+                    match (&a.frontmatter.date, &b.frontmatter.date) {
+                        (Some(a_date), Some(b_date)) => self.apply_order(a_date.cmp(b_date)),
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (None, None) => Ordering::Equal,
+                    }
+                }
+                SortKey::Title => self.apply_order(a.frontmatter.title.cmp(&b.frontmatter.title)),
+                SortKey::Path => self.apply_order(a.source_path.cmp(&b.source_path)),
+            };
+            // Fall back to the source path so the order doesn't depend on the filesystem
+            ordering.then_with(|| a.source_path.cmp(&b.source_path))
+        });
+    }
+
+    fn apply_order(&self, ordering: Ordering) -> Ordering {
+        match self.order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+// Everything `.saaru.json` can say about a single collection under `collections.<name>`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CollectionConfig {
+    pub sort_by: SortKey,
+    // Dates read best newest-first, everything else alphabetically
+    pub order: Option<SortOrder>,
+    // Template for the generated index pages of the collection. When it's picked
+    // explicitly, a missing template is an error rather than a quiet skip.
+    pub template: Option<String>,
+    // Posts per index page, 0 puts everything on one page
+    pub page_size: usize,
+    // URL prefix the index pages are generated under, defaults to the collection name
+    pub path: Option<String>,
+}
+
+pub const DEFAULT_COLLECTION_TEMPLATE: &str = "collection.jinja";
+pub const DEFAULT_COLLECTION_PAGE_SIZE: usize = 10;
+
+impl Default for CollectionConfig {
+    fn default() -> Self {
+        CollectionConfig {
+            sort_by: SortKey::Date,
+            order: None,
+            template: None,
+            page_size: DEFAULT_COLLECTION_PAGE_SIZE,
+            path: None,
+        }
+    }
+}
+
+impl CollectionConfig {
+    pub fn sort(&self) -> ListingSort {
+        let default_order = match self.sort_by {
+            SortKey::Date => SortOrder::Descending,
+            _ => SortOrder::Ascending,
+        };
+        ListingSort {
+            key: self.sort_by,
+            order: self.order.unwrap_or(default_order),
+        }
+    }
+
+    pub fn template(&self) -> &str {
+        self.template
+            .as_deref()
+            .unwrap_or(DEFAULT_COLLECTION_TEMPLATE)
+    }
+
+    pub fn page_size(&self) -> Option<usize> {
+        page_size(self.page_size)
+    }
+
+    pub fn path(&self, collection: &str) -> String {
+        self.path
+            .as_deref()
+            .unwrap_or(collection)
+            .trim_matches('/')
+            .to_string()
+    }
+}
+
+// The `tags` section of the site config
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct TagConfig {
+    // Posts per tag page, 0 (the default) puts everything on one page
+    pub page_size: usize,
+}
+
+// A page size of 0 means "don't paginate"
+pub fn page_size(size: usize) -> Option<usize> {
+    match size {
+        0 => None,
+        size => Some(size),
+    }
+}
+
+// One page of a paginated listing, handed to templates as `paginator`
+#[derive(Debug, Serialize)]
+pub struct Paginator<'a> {
+    // 1-based
+    pub current_page: usize,
+    pub total_pages: usize,
+    pub total_items: usize,
+    pub items: &'a [ThinAugmentedFrontMatter],
+    pub link: String,
+    pub previous: Option<String>,
+    pub next: Option<String>,
+    pub first: String,
+    pub last: String,
+    // Links to every page in order, for numbered page navigation
+    pub pages: Vec<String>,
+}
+
+impl Paginator<'_> {
+    // Where this page lives inside the build directory
+    pub fn write_path(&self) -> PathBuf {
+        let relative = self.link.trim_start_matches('/');
+        if relative.is_empty() || relative.ends_with('/') {
+            PathBuf::from(relative).join("index.html")
+        } else {
+            PathBuf::from(relative)
+        }
+    }
+}
+
+// Split a listing into pages. The first page lives at `first_link`, every other
+// page at whatever `page_link` returns for its (1-based) number.
+pub fn paginate<'a>(
+    items: &'a [ThinAugmentedFrontMatter],
+    page_size: Option<usize>,
+    first_link: &str,
+    page_link: impl Fn(usize) -> String,
+) -> Vec<Paginator<'a>> {
+    let chunks: Vec<&'a [ThinAugmentedFrontMatter]> = match page_size {
+        Some(size) if !items.is_empty() => items.chunks(size).collect(),
+        _ => vec![items],
+    };
+    let total_pages = chunks.len();
+
+    let pages: Vec<String> = (1..=total_pages)
+        .map(|page| {
+            if page == 1 {
+                first_link.to_string()
+            } else {
+                page_link(page)
+            }
+        })
+        .collect();
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| Paginator {
+            current_page: index + 1,
+            total_pages,
+            total_items: items.len(),
+            items: chunk,
+            link: pages[index].clone(),
+            previous: index.checked_sub(1).map(|previous| pages[previous].clone()),
+            next: pages.get(index + 1).cloned(),
+            first: pages[0].clone(),
+            last: pages[total_pages - 1].clone(),
+            pages: pages.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posts(count: usize) -> Vec<ThinAugmentedFrontMatter> {
+        (1..=count)
+            .map(|number| ThinAugmentedFrontMatter {
+                frontmatter: serde_json::from_str("{}").unwrap(),
+                source_path: format!("src/posts/{}.md", number),
+                write_path: format!("build/posts/{}.html", number),
+                link: format!("/posts/{}.html", number),
+                is_draft: false,
+            })
+            .collect()
+    }
+
+    fn paginate_posts(items: &[ThinAugmentedFrontMatter], size: usize) -> Vec<Paginator<'_>> {
+        paginate(items, page_size(size), "/x/", |page| {
+            format!("/x/page/{}/", page)
+        })
+    }
+
+    #[test]
+    fn empty_listing_gets_one_empty_page() {
+        let items = posts(0);
+        let pages = paginate_posts(&items, 10);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].total_pages, 1);
+        assert_eq!(pages[0].total_items, 0);
+        assert!(pages[0].items.is_empty());
+        assert_eq!(pages[0].previous, None);
+        assert_eq!(pages[0].next, None);
+    }
+
+    #[test]
+    fn exact_multiple_of_the_page_size() {
+        let items = posts(4);
+        let pages = paginate_posts(&items, 2);
+        assert_eq!(pages.len(), 2);
+        assert!(pages.iter().all(|page| page.items.len() == 2));
+        assert_eq!(pages[1].next, None);
+        assert_eq!(pages[1].pages, vec!["/x/", "/x/page/2/"]);
+    }
+
+    #[test]
+    fn page_size_zero_puts_everything_on_one_page() {
+        let items = posts(25);
+        let pages = paginate_posts(&items, 0);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].items.len(), 25);
+        assert_eq!(pages[0].link, "/x/");
+    }
+
+    #[test]
+    fn links_between_pages() {
+        let items = posts(5);
+        let pages = paginate_posts(&items, 2);
+        assert_eq!(pages.len(), 3);
+
+        let first = &pages[0];
+        assert_eq!(first.current_page, 1);
+        assert_eq!(first.link, "/x/");
+        assert_eq!(first.previous, None);
+        assert_eq!(first.next.as_deref(), Some("/x/page/2/"));
+        assert_eq!(first.first, "/x/");
+        assert_eq!(first.last, "/x/page/3/");
+
+        let last = &pages[2];
+        assert_eq!(last.current_page, 3);
+        assert_eq!(last.items.len(), 1);
+        assert_eq!(last.items[0].source_path, "src/posts/5.md");
+        assert_eq!(last.previous.as_deref(), Some("/x/page/2/"));
+        assert_eq!(last.next, None);
+        assert_eq!(last.first, "/x/");
+        assert_eq!(last.last, "/x/page/3/");
+    }
+
+    #[test]
+    fn write_paths() {
+        let items = posts(3);
+        let pages = paginate_posts(&items, 2);
+        assert_eq!(pages[0].write_path(), PathBuf::from("x/index.html"));
+        assert_eq!(pages[1].write_path(), PathBuf::from("x/page/2/index.html"));
+
+        let tag_pages = paginate(&items, None, "/tags/t.html", |page| {
+            format!("/tags/t/page/{}/", page)
+        });
+        assert_eq!(tag_pages[0].write_path(), PathBuf::from("tags/t.html"));
+    }
+}
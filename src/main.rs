@@ -1,65 +1,291 @@
-use crate::arguments::SaaruArguments;
-use crate::saaru::SaaruInstance;
-use clap::Parser;
+use clap::{ArgAction, Args, Parser, Subcommand};
 use log::LevelFilter;
+use saaru::{NewPost, Result, Site, SiteBuilder};
 use std::path::PathBuf;
-use std::time;
+use std::process;
 
-mod arguments;
-mod frontmatter;
-mod saaru;
-mod utils;
-
-/// modal
+/// A static site generator for fun and profit
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Arguments {
-    /// This is the base path
-    #[arg(short, long)]
+#[command(author, version, about, long_about = None, propagate_version = true)]
+struct Cli {
+    /// The site's folder, with its config file and source, template and static directories
+    #[arg(short, long, global = true, default_value = ".")]
     base_path: PathBuf,
 
+    #[arg(short, long, global = true)]
+    /// Use this config file instead of looking for `.saaru.json`, `saaru.toml` or `saaru.yaml` in the base path
+    config: Option<PathBuf>,
+
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    /// Log more, `-v` for debug output and `-vv` for everything
+    verbose: u8,
+
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    /// Only log warnings and errors
+    quiet: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build the site into the build directory
+    Build(BuildArgs),
+    /// Build the site, serve it, and re-render it and reload the browser on every change
+    Serve {
+        #[command(flatten)]
+        build: BuildArgs,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Build the site, then re-render it on every change, for use with your own web server
+    Watch(BuildArgs),
+    /// Start a new site, or a new post in an existing one
+    #[command(subcommand)]
+    New(NewCommand),
+    /// Look for problems in the site without building it: broken frontmatter, missing
+    /// templates, outputs written over each other and broken internal links
+    Check {
+        #[command(flatten)]
+        directories: DirectoryArgs,
+
+        #[arg(long)]
+        /// Check pages marked `wip: true` too
+        drafts: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum NewCommand {
+    /// Create a site skeleton in a new directory
+    Site {
+        /// Where to create the site
+        directory: PathBuf,
+    },
+    /// Create a markdown file with frontmatter in the source directory
+    Post {
+        /// Where to put the post, relative to the source directory (`.md` is added if there's no extension)
+        path: PathBuf,
+
+        #[arg(long)]
+        /// The post's title
+        title: String,
+
+        #[arg(long)]
+        /// A one line description of the post
+        description: Option<String>,
+
+        #[arg(long = "tag")]
+        /// Tag the post, can be given more than once
+        tags: Vec<String>,
+
+        #[arg(long = "collection")]
+        /// Put the post in a collection, can be given more than once
+        collections: Vec<String>,
+
+        #[arg(long)]
+        /// Template to render the post with, instead of the site's default
+        template: Option<String>,
+
+        #[arg(long)]
+        /// Mark the post `wip: true`, so it's only built with `--drafts`
+        draft: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+struct DirectoryArgs {
     #[arg(long)]
-    /// Having this on spawns both the web server and the live reloader
-    live_reload: bool,
+    /// Markdown source directory, overriding `directories.source` in the config
+    source: Option<PathBuf>,
 
     #[arg(long)]
-    /// Having this on only turns on the live re-render, assuming you have your own web server
-    live_rerender: bool,
+    /// Template directory, overriding `directories.templates` in the config
+    templates: Option<PathBuf>,
+
+    #[arg(long = "static")]
+    /// Static file directory, overriding `directories.static` in the config
+    static_files: Option<PathBuf>,
 
     #[arg(short, long)]
-    serve: bool,
+    /// Where to write the built site, overriding `directories.build` in the config
+    output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct BuildArgs {
+    #[command(flatten)]
+    directories: DirectoryArgs,
+
+    #[arg(short, long)]
+    /// Don't stop at the first broken file; report every error at the end. Nothing is written if any file fails
+    keep_going: bool,
+
+    #[arg(long)]
+    /// Include pages marked `wip: true` in the build, tags and collections
+    drafts: bool,
+
+    #[arg(long)]
+    /// Ignore the build cache and render every page, even the ones that haven't changed
+    no_cache: bool,
+
+    #[arg(long)]
+    /// Empty the build directory before building (except preserved files like `CNAME`)
+    clean: bool,
+}
+
+#[derive(Args, Debug)]
+struct ServerArgs {
+    #[arg(long)]
+    /// Address the server listens on, overriding `server.address` in the config.
+    /// Use 0.0.0.0 to preview from other devices on the network.
+    host: Option<String>,
+
+    #[arg(long)]
+    /// Port the server listens on (or the next free one), overriding `server.port` in the config
+    port: Option<u16>,
+
+    #[arg(long)]
+    /// Serve the site under this path, like `/my-project`, overriding `server.prefix` in the config
+    prefix: Option<String>,
 }
 
 fn main() {
+    let cli = Cli::parse();
+
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
     simple_logger::SimpleLogger::new()
-        .with_level(LevelFilter::Info)
+        .with_level(level)
         .env()
         .init()
         .unwrap();
     log::debug!("Initialized Logger");
+    log::debug!("Command Line Arguments -> {:?}", &cli);
 
-    let commandline_arguments = Arguments::parse();
-    log::debug!("Command Line Arguments -> {:?}", &commandline_arguments);
+    if let Err(e) = run(cli) {
+        log::error!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let site = Site::builder().base_path(cli.base_path);
+    let site = match cli.config {
+        Some(config) => site.config_file(config),
+        None => site,
+    };
+
+    match cli.command {
+        Command::Build(build) => {
+            let mut site = with_build_args(site, build).build()?;
+            println!("{}", site.render()?);
+            Ok(())
+        }
+        Command::Serve { build, server } => {
+            let mut site = with_build_args(site, build).live_reload(true);
+            if let Some(host) = server.host {
+                site = site.host(host);
+            }
+            if let Some(port) = server.port {
+                site = site.port(port);
+            }
+            if let Some(prefix) = server.prefix {
+                site = site.prefix(prefix);
+            }
+            watch(site)
+        }
+        Command::Watch(build) => watch(with_build_args(site, build).live_rerender(true)),
+        Command::New(NewCommand::Site { directory }) => {
+            saaru::new_site(&directory)?;
+            println!(
+                "Created a new site in {}, run `saaru serve -b {}` to see it",
+                directory.display(),
+                directory.display()
+            );
+            Ok(())
+        }
+        Command::New(NewCommand::Post {
+            path,
+            title,
+            description,
+            tags,
+            collections,
+            template,
+            draft,
+        }) => {
+            let post = NewPost {
+                title,
+                description,
+                tags,
+                collections,
+                template,
+                draft,
+            };
+            let path = site.new_post(path, &post)?;
+            println!("Created {}", path.display());
+            Ok(())
+        }
+        Command::Check {
+            directories,
+            drafts,
+        } => {
+            let mut site = with_directories(site, directories).drafts(drafts).build()?;
+            let report = site.check()?;
+            println!("{}", report);
+            // Nonzero on errors, so it can stop a commit or a CI run. Warnings don't count.
+            if !report.is_ok() {
+                process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
 
-    // Check for options
-    if commandline_arguments.live_reload && commandline_arguments.live_rerender {
-        log::error!("Cannot have both Live Reload (--live-reload) AND Live Re-Render (--live-rerender) on at once!");
-        panic!();
+fn with_directories(mut site: SiteBuilder, directories: DirectoryArgs) -> SiteBuilder {
+    if let Some(source) = directories.source {
+        site = site.source_dir(source);
+    }
+    if let Some(templates) = directories.templates {
+        site = site.template_dir(templates);
     }
+    if let Some(static_files) = directories.static_files {
+        site = site.static_dir(static_files);
+    }
+    if let Some(output) = directories.output {
+        site = site.build_dir(output);
+    }
+    site
+}
 
-    // Expect to see a `.saaru.json` file here in the base path
-    let args = SaaruArguments::new(
-        commandline_arguments.base_path,
-        commandline_arguments.live_reload,
-        commandline_arguments.live_rerender,
-    );
-    let mut instance = SaaruInstance::new(args);
-    instance.set_template_environment();
+fn with_build_args(site: SiteBuilder, build: BuildArgs) -> SiteBuilder {
+    with_directories(site, build.directories)
+        .keep_going(build.keep_going)
+        .drafts(build.drafts)
+        .no_cache(build.no_cache)
+        .clean(build.clean)
+}
+
+// Build once, then keep re-rendering on every change
+fn watch(site: SiteBuilder) -> Result<()> {
+    let mut site = site.build()?;
 
-    let start = time::Instant::now();
-    instance.render_pipeline();
-    let end = time::Instant::now();
-    println!("Total Time Taken -> {:?}", end - start);
+    // With live reload on, a broken site can still be fixed while watching it
+    let failed_build = match site.render() {
+        Ok(report) => {
+            println!("{}", report);
+            None
+        }
+        Err(e) => {
+            log::error!("{}", e);
+            Some(e)
+        }
+    };
 
     //  Implementing Browser-Side Live-Reload
     //
@@ -108,7 +334,5 @@ fn main() {
     //            that the web server should reload.
     //  [4-R] ->  The Web server recieves the `SaaruEvent::reload` and reloads on the browser side.
 
-    if commandline_arguments.live_reload || commandline_arguments.live_rerender {
-        instance.orchestrator();
-    }
+    site.watch(failed_build)
 }
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use crate::error::SaaruError;
+use crate::feed::escape_xml;
+
+// One failure, broken down into what the overlay shows
+struct Failure {
+    file: PathBuf,
+    template: Option<String>,
+    line: Option<usize>,
+    message: String,
+}
+
+fn failures(error: &SaaruError, found: &mut Vec<Failure>) {
+    let failure = match error {
+        SaaruError::Build { errors } => {
+            for error in errors {
+                failures(error, found);
+            }
+            return;
+        }
+        SaaruError::FrontMatter {
+            path,
+            line,
+            message,
+        } => Failure {
+            file: path.clone(),
+            template: None,
+            line: *line,
+            message: format!("invalid frontmatter: {}", message),
+        },
+        SaaruError::TemplateNotFound { path, template } => Failure {
+            file: path.clone(),
+            template: Some(template.clone()),
+            line: None,
+            message: "template not found in the template directory".to_string(),
+        },
+        // minijinja knows which template (maybe one that's included) and line it broke at
+        SaaruError::Template {
+            path,
+            template,
+            source,
+        } => Failure {
+            file: path.clone(),
+            template: Some(source.name().unwrap_or(template).to_string()),
+            line: source.line(),
+            message: match source.detail() {
+                Some(detail) => format!("{}: {}", source.kind(), detail),
+                None => source.kind().to_string(),
+            },
+        },
+        SaaruError::Io { path, source } => Failure {
+            file: path.clone(),
+            template: None,
+            line: None,
+            message: format!("I/O error: {}", source),
+        },
+        SaaruError::Config { path, message } => Failure {
+            file: path.clone(),
+            template: None,
+            line: None,
+            message: format!("configuration error: {}", message),
+        },
+        SaaruError::Watch { path, source } => Failure {
+            file: path.clone(),
+            template: None,
+            line: None,
+            message: format!("could not watch for changes: {}", source),
+        },
+    };
+    found.push(failure);
+}
+
+// The box shown on top of every page while the last rebuild is broken. The next
+// successful rebuild reloads the page without it.
+pub fn render_overlay(error: &SaaruError) -> String {
+    let mut found = Vec::new();
+    failures(error, &mut found);
+
+    let mut html = String::from(
+        "<div id=\"saaru-error-overlay\" style=\"position:fixed;inset:0;z-index:2147483647;overflow:auto;padding:2em;background:rgba(20,20,20,0.92);color:#f8f8f2;font:14px/1.5 monospace;\">\n",
+    );
+    html.push_str(&format!(
+        "<h2 style=\"color:#ff6b6b;margin-top:0;\">Build failed ({} error{})</h2>\n",
+        found.len(),
+        if found.len() == 1 { "" } else { "s" }
+    ));
+    for failure in found {
+        html.push_str("<div style=\"margin-bottom:1.5em;\">\n");
+        let mut location = escape_xml(&failure.file.display().to_string());
+        if let Some(template) = &failure.template {
+            location.push_str(&format!(" &middot; template {}", escape_xml(template)));
+        }
+        if let Some(line) = failure.line {
+            location.push_str(&format!(" &middot; line {}", line));
+        }
+        html.push_str(&format!(
+            "<div style=\"color:#8be9fd;\">{}</div>\n",
+            location
+        ));
+        html.push_str(&format!(
+            "<pre style=\"white-space:pre-wrap;margin:0.5em 0 0;background:none;color:inherit;\">{}</pre>\n",
+            escape_xml(&failure.message)
+        ));
+        html.push_str("</div>\n");
+    }
+    html.push_str("<p style=\"color:#aaa;\">Fix it and save, this goes away once the site builds again.</p>\n");
+    html.push_str("</div>\n");
+    html
+}
+
+// Put the overlay at the end of a page's body, or after everything if it hasn't got one
+pub fn inject_overlay(page: &[u8], overlay: &str) -> Vec<u8> {
+    let page = String::from_utf8_lossy(page);
+    let injected = match page.rfind("</body>") {
+        Some(end) => format!("{}{}{}", &page[..end], overlay, &page[end..]),
+        None => format!("{}{}", page, overlay),
+    };
+    injected.into_bytes()
+}
+
+// A page to show the overlay on when there's nothing else to show it on
+pub fn overlay_page(overlay: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Build failed</title></head>\n<body>\n{}</body>\n</html>\n",
+        overlay
+    )
+}
@@ -1,35 +1,56 @@
-use axum::routing::IntoMakeService;
+use axum::body::{boxed, Body, Full, HttpBody};
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{Request, StatusCode, Uri};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::{get, IntoMakeService};
 use axum::{http, routing::get_service, Router};
 use comrak::{markdown_to_html, ComrakOptions};
-use crossbeam::channel::unbounded;
+use crossbeam::channel::{unbounded, RecvTimeoutError};
 use gray_matter::{engine::YAML, Matter};
 use minijinja::{context, path_loader, value::Value, Environment};
-use notify::event::{AccessKind, ModifyKind};
+use notify::event::{AccessKind, ModifyKind, RenameMode};
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use notify::{Error, Event};
+use notify::{Event, EventKind};
+use percent_encoding::percent_decode_str;
 use tower::layer::util::Stack;
+use tower::{service_fn, ServiceExt};
 use tower_http::services::ServeDir;
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_livereload::LiveReloadLayer;
 use walkdir::WalkDir;
 
-use std::borrow::BorrowMut;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Read, Write};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::path::{Path, PathBuf};
+use std::net::TcpListener;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time;
 
 use crate::arguments::SaaruArguments;
+use crate::cache::{hash_bytes, BuildCache, BuildManifest, Fingerprint};
+use crate::check::{broken_links, duplicate_outputs, CheckReport, DryRun, Problem};
+use crate::config::MarkdownConfig;
+use crate::error::{Result, SaaruError};
+use crate::feed::{render_atom, render_rss, FeedChannel, FeedConfig, FeedEntry};
 use crate::frontmatter::{AugmentedFrontMatter, FrontMatter, ThinAugmentedFrontMatter};
-use crate::utils::copy_recursively;
+use crate::listing::{page_size, paginate, ListingSort, Paginator};
+use crate::overlay::{inject_overlay, overlay_page, render_overlay};
+use crate::site::BuildReport;
+use crate::sitemap::{page_url, render_robots_txt, render_sitemap, SitemapEntry};
+use crate::templates::TemplateGraph;
+use crate::utils::{
+    is_contained, is_editor_temp_file, link_or_copy, remove_dir_if_exists, replace_file,
+    resolve_path, sibling_path,
+};
 
 // This is the main implementation struct for Saaru
 #[derive(Debug)]
 pub enum SaaruEvent {
-    FileChanged(Result<Event, Error>),
+    FileChanged(notify::Result<Event>),
     FileReRenderCompleted,
 }
 
@@ -45,6 +66,26 @@ pub struct SaaruInstance {
     collection_map: HashMap<String, Vec<ThinAugmentedFrontMatter>>,
     tag_map: HashMap<String, Vec<ThinAugmentedFrontMatter>>,
     pub frontmatter_map: HashMap<String, AugmentedFrontMatter>,
+    // Which feeds to generate, and the site details that go in them
+    feeds: Option<(FeedConfig, FeedChannel)>,
+    // Where outputs actually get written: a staging directory during a full build,
+    // which replaces the build directory once everything has been rendered
+    output_dir: PathBuf,
+    // Every file written during the current build, absolute paths in the build directory
+    written_files: Mutex<HashSet<PathBuf>>,
+    counters: BuildCounters,
+    // Set while checking the site, to keep outputs in memory instead of writing them
+    dry_run: Option<Mutex<DryRun>>,
+    // Fingerprints from earlier builds, to skip outputs whose inputs haven't changed
+    build_cache: Mutex<BuildCache>,
+    // What the last build left in the build directory, to prune what's no longer produced
+    manifest: BuildManifest,
+    // Hash of what every page depends on, like the config
+    build_fingerprint: Fingerprint,
+    // Hash of the tag and collection listings handed to every template as `base`
+    listings_hash: String,
+    // Which templates render through which, to know what a template edit affects
+    template_graph: TemplateGraph,
     // Keep this default template
     default_template: String,
     // serialize and generate the default context ahead of time to have faster renders
@@ -57,6 +98,26 @@ pub struct SaaruInstance {
     render_channel_consumer: crossbeam::channel::Receiver<Option<(String, AugmentedFrontMatter)>>,
 }
 
+// What the current build actually did, for its report
+#[derive(Debug, Default)]
+struct BuildCounters {
+    rendered: AtomicUsize,
+    copied: AtomicUsize,
+    pruned: AtomicUsize,
+}
+
+// The overlay shown on every page while the last rebuild is broken
+type ErrorOverlay = Arc<Mutex<Option<String>>>;
+
+// What static hosts serve for URLs that don't exist
+const NOT_FOUND_PAGE: &str = "404.html";
+
+// How many ports after the configured one to try when it's taken
+const PORT_ATTEMPTS: u16 = 20;
+
+// How long the filesystem has to stay quiet before a batch of changes gets rendered
+const DEBOUNCE: time::Duration = time::Duration::from_millis(100);
+
 const LOGO: &str = r"
    ____
   / __/__ ____ _______ __
@@ -71,35 +132,30 @@ impl SaaruInstance {
      * functions for Saaru
      */
 
-    pub fn new(args: SaaruArguments) -> Self {
+    pub fn new(args: SaaruArguments) -> Result<Self> {
         log::info!("{}", LOGO);
         log::info!("Printed Logo");
 
-        let mut options = ComrakOptions::default();
-        options.extension.front_matter_delimiter = Some("---".to_owned());
-
-        // Allow raw HTML!
-        options.render.unsafe_ = true;
-        options.extension.table = true;
-        options.extension.autolink = true;
-        options.extension.tasklist = true;
-        // options.extension.tagfilter = true;
-        options.extension.footnotes = true;
-        options.extension.strikethrough = true;
-        options.extension.description_lists = true;
-        // options.extension.superscript = true;
-        // options.extension.tagfilter = true;
-
-        // TODO see where this can fail
-        let default_template = args.json_content["metadata"]["templates"]["default"]
-            .as_str()
-            .unwrap()
-            .to_string();
+        let options = markdown_options(&args.config.markdown);
+
+        let default_template = args.config.metadata.templates.default.clone();
         log::info!("Default Jinja Template -> {:?}", &default_template);
 
+        let feeds = feeds(&args)?;
+        let parallel_render_threads = args.config.threads;
+
+        let build_cache = if args.no_cache {
+            BuildCache::default()
+        } else {
+            BuildCache::load(&args.base_dir)
+        };
+
+        let manifest = BuildManifest::load(&args.base_dir);
+        let output_dir = args.build_dir.clone();
+
         let (tx, rx) = unbounded::<Option<(String, AugmentedFrontMatter)>>();
 
-        SaaruInstance {
+        Ok(SaaruInstance {
             template_env: Environment::new(),
             frontmatter_parser: Matter::new(),
             markdown_options: options,
@@ -109,21 +165,72 @@ impl SaaruInstance {
             collection_map: HashMap::new(),
             tag_map: HashMap::new(),
             frontmatter_map: HashMap::new(),
+            feeds,
+            output_dir,
+            written_files: Mutex::new(HashSet::new()),
+            counters: BuildCounters::default(),
+            dry_run: None,
+            build_cache: Mutex::new(build_cache),
+            manifest,
+            build_fingerprint: Fingerprint::new(),
+            listings_hash: String::new(),
+            template_graph: TemplateGraph::default(),
             base_context: context!(),
             default_template,
-            // TODO Read from config later
-            parallel_render_threads: 10,
+            parallel_render_threads,
             render_channel_producer: tx,
             render_channel_consumer: rx,
-        }
+        })
     }
 
-    pub fn validate_source_structure(&self) -> bool {
+    pub fn validate_source_structure(&self) -> Result<()> {
         // Check if the source directory structure is as it's supposed to be
         // TODO later validate for the right files existing
-        self.arguments.source_dir.exists()
-            && self.arguments.template_dir.exists()
-            && self.arguments.static_dir.exists()
+        let arguments = &self.arguments;
+        let inputs = [
+            ("source", &arguments.source_dir),
+            ("templates", &arguments.template_dir),
+            ("static", &arguments.static_dir),
+        ];
+        for (name, dir) in inputs {
+            if !dir.is_dir() {
+                return Err(SaaruError::config(
+                    dir,
+                    format!(
+                        "{} directory is missing. Point `directories.{}` in the config (or --{}) somewhere else.",
+                        name, name, name
+                    ),
+                ));
+            }
+        }
+
+        // The build directory gets written over, so it can't overlap with anything we read from.
+        // A build directory inside the static directory would also copy itself forever.
+        let build_dir = &arguments.build_dir;
+        if build_dir == &arguments.base_dir {
+            return Err(SaaruError::config(
+                build_dir,
+                "the build directory can't be the base path itself",
+            ));
+        }
+        if build_dir.exists() && !build_dir.is_dir() {
+            return Err(SaaruError::config(
+                build_dir,
+                "the build directory exists, but isn't a directory",
+            ));
+        }
+        for (name, dir) in inputs {
+            if build_dir.starts_with(dir) || dir.starts_with(build_dir) {
+                return Err(SaaruError::config(
+                    build_dir,
+                    format!(
+                        "the build directory can't overlap with the {} directory {:?}",
+                        name, dir
+                    ),
+                ));
+            }
+        }
+        Ok(())
     }
 
     pub fn set_template_environment(&mut self) {
@@ -133,68 +240,121 @@ impl SaaruInstance {
         log::info!("Initialized Template Environment");
     }
 
-    pub fn get_write_path(&self, entry_path: &Path) -> PathBuf {
+    pub fn get_write_path(&self, entry_path: &Path) -> Result<PathBuf> {
         // Generate the final write path ->
         // Input: src/posts/a.md
         // Output: build/posts/a.html
 
-        let mut write_path = entry_path.to_path_buf();
-
-        write_path = write_path
+        let mut write_path = entry_path
             .strip_prefix(&self.arguments.source_dir)
-            .unwrap()
+            .map_err(|_| {
+                SaaruError::config(
+                    entry_path,
+                    format!(
+                        "file is not inside the source directory {:?}",
+                        self.arguments.source_dir
+                    ),
+                )
+            })?
             .to_path_buf();
 
         write_path.set_extension("html");
 
         // Append the write path into the base directory
-        let final_write_path = self.arguments.build_dir.join(&write_path);
-        final_write_path
+        Ok(self.arguments.build_dir.join(&write_path))
     }
 
-    pub fn get_relative_path_from_write_path(&self, write_path: &PathBuf) -> PathBuf {
+    pub fn get_relative_path_from_write_path(&self, write_path: &Path) -> Result<PathBuf> {
         // Strip the base directory from the write path, giving you the build-local
         // Hyperlink you can drop in to the HTML to have valid links
         // Assumes input is coming from the get_write_path function
         let dir_path = write_path
             .strip_prefix(&self.arguments.build_dir)
-            .unwrap()
+            .map_err(|_| {
+                SaaruError::config(
+                    write_path,
+                    format!(
+                        "output is not inside the build directory {:?}",
+                        self.arguments.build_dir
+                    ),
+                )
+            })?
             .to_path_buf();
         let mut relative = PathBuf::from("/");
         relative = relative.join(dir_path);
         log::info!("Stripped Relative Path -> {:?}", relative);
-        relative
+        Ok(relative)
     }
 
-    pub fn preprocess_file_data(&mut self, filename: &Path) {
-        let file = File::open(filename).unwrap();
+    pub fn preprocess_file_data(&mut self, filename: &Path) -> Result<()> {
+        if let Some(page) = self.read_page(filename)? {
+            self.insert_page(page);
+        }
+        Ok(())
+    }
+
+    // Read and parse a markdown file. Drafts come back as `None` unless building with --drafts.
+    fn read_page(&self, filename: &Path) -> Result<Option<AugmentedFrontMatter>> {
+        let file = File::open(filename).map_err(|e| SaaruError::io(filename, e))?;
         let mut reader = BufReader::new(file);
         let mut markdown_file_content = String::new();
-        reader.read_to_string(&mut markdown_file_content).unwrap();
+        reader
+            .read_to_string(&mut markdown_file_content)
+            .map_err(|e| SaaruError::io(filename, e))?;
 
         // Parse the frontmatter
-        let parsed_frontmatter: FrontMatter = self
-            .frontmatter_parser
-            .parse(&markdown_file_content)
-            .data
-            .unwrap()
-            .deserialize()
-            .unwrap();
+        let parsed_frontmatter =
+            FrontMatter::from_markdown(&self.frontmatter_parser, filename, &markdown_file_content)?;
+
+        // Collections and tags become paths in the build (index pages, tag pages and
+        // their feeds), so they can't point out of it
+        let listings = [
+            ("collection", &parsed_frontmatter.collections),
+            ("tag", &parsed_frontmatter.tags),
+        ];
+        for (kind, names) in listings {
+            for name in names.iter().flatten() {
+                if !is_contained(Path::new(name)) {
+                    return Err(SaaruError::FrontMatter {
+                        path: filename.to_path_buf(),
+                        line: None,
+                        message: format!(
+                            "invalid {} {:?}, {} names can't contain `..` or start with `/`",
+                            kind, name, kind
+                        ),
+                    });
+                }
+            }
+        }
 
         let cleaned_markdown = markdown_file_content;
         let filename_str = filename.display().to_string();
 
-        let write_path = self.get_write_path(filename);
-        let relative_build_path = self.get_relative_path_from_write_path(&write_path);
+        // Drafts stay out of the frontmatter map entirely, so they never get rendered
+        // or show up in the tag and collection listings
+        if parsed_frontmatter.is_draft() && !self.arguments.drafts {
+            log::info!(
+                "Skipping draft {:?} (build with --drafts to include it)",
+                filename
+            );
+            return Ok(None);
+        }
 
-        let aug_fm_struct = AugmentedFrontMatter {
-            file_content: cleaned_markdown.clone(),
-            frontmatter: parsed_frontmatter.clone(),
-            source_path: filename_str.clone(),
+        let write_path = self.get_write_path(filename)?;
+        let relative_build_path = self.get_relative_path_from_write_path(&write_path)?;
+
+        Ok(Some(AugmentedFrontMatter {
+            file_content: cleaned_markdown,
+            frontmatter: parsed_frontmatter,
+            source_path: filename_str,
             write_path: write_path.display().to_string(),
             relative_build_path: relative_build_path.display().to_string(),
-        };
+        }))
+    }
 
+    // Add a page to the frontmatter map, and to the listing of every tag and collection it's in
+    fn insert_page(&mut self, aug_fm_struct: AugmentedFrontMatter) {
+        let filename_str = aug_fm_struct.source_path.clone();
         let tag_copy = aug_fm_struct.clone();
         let collection_copy = aug_fm_struct.clone();
 
@@ -243,163 +403,1001 @@ impl SaaruInstance {
         }
     }
 
-    pub fn convert_markdown_to_html(&self, markdown: &String) -> String {
-        let parser = markdown_to_html(markdown, &self.markdown_options);
-        parser
+    // Forget a page, along with its entries in the tag and collection listings.
+    // Listings it was the last page of go away entirely.
+    fn remove_page(&mut self, source_path: &str) -> Option<AugmentedFrontMatter> {
+        for listing in self
+            .tag_map
+            .values_mut()
+            .chain(self.collection_map.values_mut())
+        {
+            listing.retain(|entry| entry.source_path != source_path);
+        }
+        self.tag_map.retain(|_, listing| !listing.is_empty());
+        self.collection_map.retain(|_, listing| !listing.is_empty());
+        self.frontmatter_map.remove(source_path)
+    }
+
+    // Put every tag and collection listing in its configured order
+    pub fn sort_listings(&mut self) {
+        for listing in self.tag_map.values_mut() {
+            ListingSort::default().sort(listing);
+        }
+        for (collection, listing) in self.collection_map.iter_mut() {
+            self.arguments
+                .config
+                .collections
+                .get(collection)
+                .map(|config| config.sort())
+                .unwrap_or_default()
+                .sort(listing);
+        }
+    }
+
+    pub fn convert_markdown_to_html(&self, markdown: &str) -> String {
+        markdown_to_html(markdown, &self.markdown_options)
     }
 
     pub fn render_file_from_frontmatter(
         &self,
         input_aug_frontmatter: &AugmentedFrontMatter,
-    ) -> String {
+    ) -> Result<String> {
         // Conver the Markdown to HTML
         let html_output = self.convert_markdown_to_html(&input_aug_frontmatter.file_content);
 
         // Fetch the Template
-        let rendered_template = match &input_aug_frontmatter.frontmatter.template {
-            Some(template_name) => self.template_env.get_template(&template_name).unwrap(),
-            None => self
-                .template_env
-                .get_template(&self.default_template)
-                .unwrap(),
-        };
+        let template_name = self.page_template(input_aug_frontmatter);
+        let source_path = &input_aug_frontmatter.source_path;
+        let rendered_template = self
+            .template_env
+            .get_template(template_name)
+            .map_err(|e| SaaruError::template(source_path, template_name, e))?;
 
         // Render the template
-        let rendered_final_html = rendered_template
+        rendered_template
             .render(context!(
                 frontmatter => input_aug_frontmatter.frontmatter,
                 postcontent => html_output,
+                is_draft => input_aug_frontmatter.frontmatter.is_draft(),
                 base => &self.base_context
             ))
-            .unwrap();
+            .map_err(|e| SaaruError::template(source_path, template_name, e))
+    }
 
-        // Copy just for fun
-        rendered_final_html
+    // The template a page is rendered with
+    fn page_template<'a>(&'a self, page: &'a AugmentedFrontMatter) -> &'a str {
+        page.frontmatter
+            .template
+            .as_deref()
+            .unwrap_or(&self.default_template)
+    }
+
+    // The build fingerprint, plus the sources of every template `template` renders through,
+    // plus the listings if any of those templates show them
+    fn template_fingerprint(&self, template: &str) -> Fingerprint {
+        let mut fingerprint = self.build_fingerprint.clone();
+        self.template_graph
+            .add_to_fingerprint(template, &mut fingerprint);
+        if self.template_graph.reads_listings(template) {
+            fingerprint.add(&self.listings_hash);
+        }
+        fingerprint
     }
 
-    pub fn write_html_to_file(&self, output_filename: PathBuf, input_html: String) {
+    pub fn write_html_to_file(&self, output_filename: PathBuf, input_html: String) -> Result<()> {
         // Create the file and folder if it doesn't exist, write it to disk
 
         // Generate the output path from the build directory and the given output filename
-        let mut output_path = self.arguments.build_dir.clone();
-        output_path.push(output_filename);
+        let output_path = self.build_path(output_filename)?;
+
+        if let Some(dry_run) = &self.dry_run {
+            dry_run
+                .lock()
+                .expect("dry run lock poisoned")
+                .record(output_path.clone(), Some(input_html));
+            self.written_files
+                .lock()
+                .expect("written files lock poisoned")
+                .insert(output_path);
+            return Ok(());
+        }
+
+        let disk_path = self.output_path(&output_path);
 
         // Create all the necessary directories that need to be created
-        let current_prefix = output_path.parent().unwrap();
+        if let Some(current_prefix) = disk_path.parent() {
+            fs::create_dir_all(current_prefix).map_err(|e| SaaruError::io(current_prefix, e))?;
+        }
 
-        fs::create_dir_all(current_prefix).unwrap();
+        // Whatever the cache remembered about this output no longer describes what's on disk
+        self.build_cache
+            .lock()
+            .expect("build cache lock poisoned")
+            .outputs
+            .remove(&self.cache_key(&output_path));
+
+        // Create the file itself, and write it through a buffered writer
+        replace_file(&disk_path, |temporary| {
+            let mut writer = BufWriter::new(fs::File::create(temporary)?);
+            writer.write_all(input_html.as_bytes())?;
+            writer.flush()
+        })
+        .map_err(|e| SaaruError::io(&output_path, e))?;
+        log::info!("SUCCESS: Wrote to {:?}", &output_path);
+        self.counters.rendered.fetch_add(1, Ordering::Relaxed);
+        self.written_files
+            .lock()
+            .expect("written files lock poisoned")
+            .insert(output_path);
+        Ok(())
+    }
 
-        // Create the file itself
-        let file = fs::File::create(&output_path).unwrap();
+    // The path of an output in the build directory. Whatever a page or the config asked
+    // for, nothing gets written outside of it.
+    fn build_path(&self, output_filename: PathBuf) -> Result<PathBuf> {
+        let relative = output_filename
+            .strip_prefix(&self.arguments.build_dir)
+            .unwrap_or(&output_filename);
+        if !is_contained(relative) {
+            return Err(SaaruError::config(
+                &output_filename,
+                format!(
+                    "refusing to write outside the build directory {:?}",
+                    self.arguments.build_dir
+                ),
+            ));
+        }
+        Ok(self.arguments.build_dir.join(relative))
+    }
 
-        // create a buffered writer
-        let mut writer = BufWriter::new(file);
-        let input_bytes = input_html.as_bytes();
+    // Where a path in the build directory is written to right now
+    fn output_path(&self, build_path: &Path) -> PathBuf {
+        match build_path.strip_prefix(&self.arguments.build_dir) {
+            Ok(relative) => self.output_dir.join(relative),
+            Err(_) => self.output_dir.join(build_path),
+        }
+    }
 
-        // Write to the file
-        writer.write_all(input_bytes).expect("Could not write!");
-        log::info!("SUCCESS: Wrote to {:?}", &output_path);
+    // Outputs are cached by their path inside the build directory
+    fn cache_key(&self, output_path: &Path) -> String {
+        output_path
+            .strip_prefix(&self.arguments.build_dir)
+            .unwrap_or(output_path)
+            .display()
+            .to_string()
+    }
+
+    // Render and write an output, unless the cache says it was already built from the exact same inputs
+    fn write_if_changed(
+        &self,
+        output_filename: PathBuf,
+        fingerprint: &Fingerprint,
+        render: impl FnOnce() -> Result<String>,
+    ) -> Result<()> {
+        let output_path = self.build_path(output_filename)?;
+        let key = self.cache_key(&output_path);
+        let fingerprint = fingerprint.finish();
+
+        // A dry run renders everything, since it needs to see every output
+        let unchanged = self.dry_run.is_none()
+            && self.output_path(&output_path).is_file()
+            && self
+                .build_cache
+                .lock()
+                .expect("build cache lock poisoned")
+                .outputs
+                .get(&key)
+                == Some(&fingerprint);
+        if unchanged {
+            log::debug!("Unchanged, skipping {:?}", &output_path);
+            self.written_files
+                .lock()
+                .expect("written files lock poisoned")
+                .insert(output_path);
+            return Ok(());
+        }
+
+        self.write_html_to_file(output_path, render()?)?;
+        // Nothing hit the disk in a dry run, so there's nothing for the cache to remember
+        if self.dry_run.is_none() {
+            self.build_cache
+                .lock()
+                .expect("build cache lock poisoned")
+                .outputs
+                .insert(key, fingerprint);
+        }
+        Ok(())
+    }
+
+    // Everything that every page depends on, hashed once per build
+    fn compute_build_fingerprint(&self) -> Fingerprint {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint
+            .add(self.arguments.json_content.to_string())
+            .add(self.arguments.build_dir.display().to_string())
+            .add([self.arguments.drafts as u8]);
+        fingerprint
+    }
+
+    fn compute_listings_hash(&self) -> String {
+        // Sorted, so the hash doesn't depend on HashMap order
+        let tags: BTreeMap<&String, &Vec<ThinAugmentedFrontMatter>> = self.tag_map.iter().collect();
+        let collections: BTreeMap<&String, &Vec<ThinAugmentedFrontMatter>> =
+            self.collection_map.iter().collect();
+        let listings = serde_json::to_value((tags, collections))
+            .expect("tag and collection listings always serialize");
+        hash_bytes(listings.to_string().as_bytes())
+    }
+
+    // Regenerate the DDM context (and its hash) after the listings have changed
+    fn refresh_context(&mut self) {
+        log::debug!("Generating DDM Context...");
+        self.base_context = context!(
+            tags => &self.tag_map,
+            collections => &self.collection_map,
+            json => &self.arguments.json_content
+        );
+        self.listings_hash = self.compute_listings_hash();
+    }
+
+    // Build-relative paths of everything in the build directory that came from us,
+    // generated or copied from the static folder
+    fn produced_files(&self) -> HashSet<String> {
+        let mut produced: HashSet<String> = self
+            .written_files
+            .lock()
+            .expect("written files lock poisoned")
+            .iter()
+            .map(|path| self.cache_key(path))
+            .collect();
+        let cache = self.build_cache.lock().expect("build cache lock poisoned");
+        produced.extend(cache.static_files.keys().cloned());
+        produced
+    }
+
+    // Remember what has been built. After a complete build, also forget outputs it no longer produces.
+    fn save_build_cache(&mut self, complete: bool) -> Result<()> {
+        let produced = self.produced_files();
+        // Only a complete build knows everything that's in the build directory,
+        // anything else has to keep the files the last one left there too
+        if complete || self.manifest.build_dir != self.arguments.build_dir {
+            self.manifest.files.clear();
+        }
+        self.manifest.build_dir = self.arguments.build_dir.clone();
+        self.manifest.files.extend(produced.iter().cloned());
+        self.manifest.save(&self.arguments.base_dir)?;
+
+        let mut cache = self.build_cache.lock().expect("build cache lock poisoned");
+        if complete {
+            cache.outputs.retain(|key, _| produced.contains(key));
+        }
+        cache.save(&self.arguments.base_dir)
+    }
+
+    // Whether a build-relative path is one Saaru must never delete
+    fn is_preserved(&self, relative: &Path) -> bool {
+        self.arguments
+            .config
+            .preserve
+            .iter()
+            .any(|preserved| relative.starts_with(preserved))
+    }
+
+    // Delete the files the last build wrote that this one didn't, like the page of a
+    // post that's been deleted since, so they don't get deployed
+    fn prune_stale_outputs(&self) -> Result<()> {
+        // The manifest is about some other directory, which isn't ours to clean up
+        if self.manifest.build_dir != self.arguments.build_dir {
+            return Ok(());
+        }
+        let produced = self.produced_files();
+        let mut pruned = 0;
+        for key in self
+            .manifest
+            .files
+            .difference(&produced.into_iter().collect())
+        {
+            // Only ever delete inside the build directory, whatever the manifest says
+            if !is_contained(Path::new(key)) {
+                log::warn!(
+                    "Ignoring {:?} in the build manifest, it isn't a path inside the build directory",
+                    key
+                );
+                continue;
+            }
+            if self.is_preserved(Path::new(key)) {
+                continue;
+            }
+            let output_path = self.output_dir.join(key);
+            log::info!("Removing {:?}, nothing produces it any more", key);
+            match fs::remove_file(&output_path) {
+                Ok(()) => pruned += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(SaaruError::io(&output_path, e)),
+            }
+            self.remove_empty_directories(&output_path);
+        }
+        log::info!("Pruned {} stale files from the build directory", pruned);
+        self.counters.pruned.store(pruned, Ordering::Relaxed);
+        Ok(())
+    }
+
+    // Start a full build in a fresh staging directory next to the build directory. The
+    // previous build is hard-linked into it, so outputs that haven't changed don't have to
+    // be written again - or, for `--clean`, only the preserved paths are.
+    fn prepare_staging(&self, clean: bool) -> Result<PathBuf> {
+        let build_dir = &self.arguments.build_dir;
+        let staging = sibling_path(build_dir, "staging");
+        // Left behind by a build that crashed
+        remove_dir_if_exists(&staging).map_err(|e| SaaruError::io(&staging, e))?;
+        // The build directory may be nested somewhere that doesn't exist yet, like `ci/artifacts/site`
+        fs::create_dir_all(&staging).map_err(|e| SaaruError::io(&staging, e))?;
+
+        if !build_dir.is_dir() {
+            log::info!("Build Directory Created Successfully");
+            return Ok(staging);
+        }
+        if clean {
+            log::info!("Cleaning the build directory {:?}", build_dir);
+        }
+        let carried_over = WalkDir::new(build_dir)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| {
+                let relative = entry
+                    .path()
+                    .strip_prefix(build_dir)
+                    .expect("walkdir only yields paths inside the build directory");
+                // Keep walking into directories that have something preserved further down
+                !clean
+                    || self.is_preserved(relative)
+                    || self
+                        .arguments
+                        .config
+                        .preserve
+                        .iter()
+                        .any(|preserved| preserved.starts_with(relative))
+            });
+        for entry in carried_over {
+            let entry = entry.map_err(|e| {
+                let path = e.path().unwrap_or(build_dir).to_path_buf();
+                SaaruError::io(path, e.into())
+            })?;
+            let relative = entry
+                .path()
+                .strip_prefix(build_dir)
+                .expect("walkdir only yields paths inside the build directory");
+            let target = staging.join(relative);
+            let carried = if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)
+            } else {
+                link_or_copy(entry.path(), &target)
+            };
+            carried.map_err(|e| SaaruError::io(entry.path(), e))?;
+        }
+        Ok(staging)
+    }
+
+    // Put a finished build in place of the previous one. Both are on the same filesystem,
+    // so these are renames, and the only moment without a build directory is between them.
+    fn swap_in_staging(&self, staging: &Path) -> Result<()> {
+        let build_dir = &self.arguments.build_dir;
+        let previous = sibling_path(build_dir, "previous");
+        remove_dir_if_exists(&previous).map_err(|e| SaaruError::io(&previous, e))?;
+        if build_dir.exists() {
+            fs::rename(build_dir, &previous).map_err(|e| SaaruError::io(build_dir, e))?;
+        }
+        fs::rename(staging, build_dir).map_err(|e| SaaruError::io(staging, e))?;
+        if let Err(e) = remove_dir_if_exists(&previous) {
+            log::warn!("Couldn't remove the previous build {:?}: {}", previous, e);
+        }
+        Ok(())
     }
 
-    pub fn render_all_files(&self) {
+    pub fn render_all_files(&self) -> Result<()> {
+        match self.render_frontmatter_map().into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    // Render every page in the frontmatter map on the thread pool, returning every failure
+    fn render_frontmatter_map(&self) -> Vec<SaaruError> {
+        self.render_pages(self.frontmatter_map.values().collect())
+    }
+
+    // Render the given pages on the thread pool, returning every failure
+    fn render_pages(&self, pages: Vec<&AugmentedFrontMatter>) -> Vec<SaaruError> {
         std::thread::scope(|scope| {
             scope.spawn(|| {
-                // Render the entire map
-                for (key, val) in &self.frontmatter_map {
+                for page in pages {
                     // Key => Path
-                    let key = key.clone();
+                    let key = page.source_path.clone();
                     // Value => AugmentedFrontMatter
-                    let value = val.clone();
+                    let value = page.clone();
 
                     log::info!("Rendering file {:?} to Path {:?}", key, value.write_path);
                     self.render_channel_producer
                         .send(Some((key, value)))
-                        .unwrap();
+                        .expect("render channel closed while the instance is alive");
                 }
                 // terminate all threads
                 for _ in 0..self.parallel_render_threads {
-                    self.render_channel_producer.send(None).unwrap();
+                    self.render_channel_producer
+                        .send(None)
+                        .expect("render channel closed while the instance is alive");
                 }
             });
 
+            let mut workers = Vec::with_capacity(self.parallel_render_threads);
             for x in 0..self.parallel_render_threads {
-                // TODO Figure out clean exit later
                 log::info!("Launching Thread ID {x}");
-                scope.spawn(|| loop {
-                    match self.render_channel_consumer.recv() {
-                        Ok(Some(work)) => {
-                            let key = work.0;
-                            let val = work.1;
-                            log::info!("Rendering file {:?} to Path {:?}", key, val.write_path);
-                            let html_content = self.render_file_from_frontmatter(&val);
-                            self.write_html_to_file(PathBuf::from(&val.write_path), html_content);
-                        }
-                        Ok(None) => {
-                            log::info!("Terminating thread...");
-                            break;
+                // Workers keep draining the channel after a failure, so every thread still
+                // sees its termination message and nothing is left over for the next run
+                workers.push(scope.spawn(|| {
+                    let mut errors = Vec::new();
+                    loop {
+                        match self.render_channel_consumer.recv() {
+                            Ok(Some(work)) => {
+                                let key = work.0;
+                                let val = work.1;
+                                log::info!("Rendering file {:?} to Path {:?}", key, val.write_path);
+                                let mut fingerprint =
+                                    self.template_fingerprint(self.page_template(&val));
+                                fingerprint.add(&val.source_path).add(&val.file_content);
+                                let rendered = self.write_if_changed(
+                                    PathBuf::from(&val.write_path),
+                                    &fingerprint,
+                                    || self.render_file_from_frontmatter(&val),
+                                );
+                                if let Err(e) = rendered {
+                                    errors.push(e);
+                                }
+                            }
+                            Ok(None) => {
+                                log::info!("Terminating thread...");
+                                break;
+                            }
+                            Err(_) => break,
                         }
-                        Err(e) => panic!("gone bro {}", e),
                     }
-                });
-                // self.threads.push(join_handle);
+                    errors
+                }));
             }
-        });
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("render thread panicked"))
+                .collect::<Vec<SaaruError>>()
+        })
     }
 
-    pub fn render_individual_file(&mut self, path: &PathBuf) {
+    // Re-read a markdown file that was saved or created
+    fn reload_page(&mut self, path: &Path) -> Result<()> {
         log::info!("[LIVERELOAD] Processing file {:?}", path);
-        self.preprocess_file_data(&path);
+        // Parse before touching anything, so a broken save leaves the site as it was
+        let page = self.read_page(path)?;
+
+        // The page may have gained or lost tags and collections (or become a draft),
+        // so take out its old listing entries before putting the new ones in
+        self.remove_page(&path.display().to_string());
+        if let Some(page) = page {
+            self.insert_page(page);
+        }
+        Ok(())
+    }
 
-        let current_frontmatter = self
+    // Pick up every markdown file in a directory that just appeared in the source directory
+    fn load_new_directory(&mut self, directory: &Path) -> Result<()> {
+        log::info!("[LIVERELOAD] Processing directory {:?}", directory);
+        let mut pages = Vec::new();
+        for entry in WalkDir::new(directory) {
+            let entry = entry.map_err(|e| {
+                let path = e.path().unwrap_or(directory).to_path_buf();
+                SaaruError::io(path, e.into())
+            })?;
+            if entry.file_type().is_file()
+                && entry.path().extension().and_then(|ext| ext.to_str()) == Some("md")
+            {
+                pages.push(self.read_page(entry.path())?);
+            }
+        }
+        for page in pages.into_iter().flatten() {
+            self.remove_page(&page.source_path.clone());
+            self.insert_page(page);
+        }
+        Ok(())
+    }
+
+    // Forget every page at or under a path that's gone from the source directory.
+    // Returns whether there were any.
+    fn remove_source_path(&mut self, removed: &Path) -> bool {
+        let gone: Vec<String> = self
             .frontmatter_map
-            .get(&path.display().to_string())
-            .unwrap()
-            .clone();
-        log::debug!(
-            "[LIVERELOAD] Triggering HTML Conversion for file {:?}",
-            path
+            .keys()
+            .filter(|source_path| Path::new(source_path).starts_with(removed))
+            .cloned()
+            .collect();
+        for source_path in &gone {
+            log::info!("[LIVERELOAD] Removing page {:?}", source_path);
+            self.remove_page(source_path);
+        }
+        !gone.is_empty()
+    }
+
+    // After pages or templates were added, changed or removed on their own, re-render whatever they affect and
+    // delete the outputs nothing produces any more, like the page of a tag that's gone.
+    // Everything goes through the cache, so only the changed pages get rendered again - plus,
+    // when their listing entries changed, the pages that show the listings, their tag pages
+    // and their collection index pages.
+    fn render_changed_pages(&mut self) -> Result<()> {
+        self.sort_listings();
+        self.refresh_context();
+
+        let previous = std::mem::take(
+            &mut *self
+                .written_files
+                .lock()
+                .expect("written files lock poisoned"),
         );
-        let html_content = self.render_file_from_frontmatter(&current_frontmatter);
+        let rendered = self.render_generated_files();
+
+        let mut written_files = self
+            .written_files
+            .lock()
+            .expect("written files lock poisoned");
+        if let Err(e) = rendered {
+            // Not everything got rendered, so there's no telling what's stale
+            written_files.extend(previous);
+            return Err(e);
+        }
+        let stale: Vec<PathBuf> = previous.difference(&written_files).cloned().collect();
+        drop(written_files);
+        for output_path in stale {
+            self.remove_output(&output_path)?;
+        }
+        Ok(())
+    }
 
-        log::debug!("[LIVERELOAD] Writing to Destination for file {:?}", path);
-        self.write_html_to_file(PathBuf::from(&current_frontmatter.write_path), html_content);
+    // Everything in the build directory that doesn't come from the static folder
+    fn render_generated_files(&self) -> Result<()> {
+        if let Some(e) = self.render_frontmatter_map().into_iter().next() {
+            return Err(e);
+        }
+        self.render_tags_pages()?;
+        self.render_collection_pages()?;
+        self.render_not_found_page()?;
+        self.render_feeds()?;
+        self.render_sitemap()
     }
 
-    fn render_tags_pages(&self) {
-        // A function to render all pages for tags
-        let tag_index_template = self.template_env.get_template("tags.jinja").unwrap();
+    // Delete an output that's no longer produced, along with any directories it leaves empty
+    fn remove_output(&self, output_path: &Path) -> Result<()> {
+        let key = self.cache_key(output_path);
+        let mut cache = self.build_cache.lock().expect("build cache lock poisoned");
+        cache.outputs.remove(&key);
+        // A static file copied over the same path stays
+        if cache.static_files.contains_key(&key) {
+            return Ok(());
+        }
+        drop(cache);
+
+        log::info!("Removing {:?}, nothing produces it any more", output_path);
+        match fs::remove_file(output_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(SaaruError::io(output_path, e)),
+        }
+        self.remove_empty_directories(output_path);
+        Ok(())
+    }
+
+    // Clean up directories left empty inside the output directory, like `tags/<tag>/page/`
+    fn remove_empty_directories(&self, removed: &Path) {
+        // `starts_with` only compares components, so `<output>/../..` has to be tidied up first
+        let removed = resolve_path(Path::new(""), removed);
+        let output_dir = resolve_path(Path::new(""), &self.output_dir);
+        let mut directory = removed.parent();
+        while let Some(current) = directory {
+            if current == output_dir || !current.starts_with(&output_dir) {
+                break;
+            }
+            // Fails (and stops) as soon as a directory still has something in it
+            if fs::remove_dir(current).is_err() {
+                break;
+            }
+            directory = current.parent();
+        }
+    }
+
+    // Mirror a file (or a whole directory) deleted from the static folder into the build directory
+    fn remove_static_path(&self, removed: &Path) -> Result<()> {
+        let relative = match removed.strip_prefix(&self.arguments.static_dir) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => return Ok(()),
+        };
+        let written_files = self
+            .written_files
+            .lock()
+            .expect("written files lock poisoned")
+            .clone();
+        let mut cache = self.build_cache.lock().expect("build cache lock poisoned");
+        let gone: Vec<String> = cache
+            .static_files
+            .keys()
+            .filter(|key| Path::new(key).starts_with(&relative))
+            .cloned()
+            .collect();
+
+        for key in gone {
+            cache.static_files.remove(&key);
+            let output_path = self.arguments.build_dir.join(&key);
+            // A generated page lives at the same path, leave it be
+            if written_files.contains(&output_path) {
+                continue;
+            }
+            log::info!(
+                "Removing {:?}, it's gone from the static folder",
+                output_path
+            );
+            match fs::remove_file(&output_path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(SaaruError::io(&output_path, e)),
+            }
+            self.remove_empty_directories(&output_path);
+        }
+        Ok(())
+    }
 
-        let tag_individual_template = self.template_env.get_template("tags_page.jinja").unwrap();
+    fn render_tags_pages(&self) -> Result<()> {
+        // A function to render all pages for tags
+        let tags_template_path = self.arguments.template_dir.join("tags.jinja");
+        let tag_index_template = self
+            .template_env
+            .get_template("tags.jinja")
+            .map_err(|e| SaaruError::template(&tags_template_path, "tags.jinja", e))?;
+
+        let tags_page_template_path = self.arguments.template_dir.join("tags_page.jinja");
+        let tag_individual_template = self
+            .template_env
+            .get_template("tags_page.jinja")
+            .map_err(|e| SaaruError::template(&tags_page_template_path, "tags_page.jinja", e))?;
 
         let base_tags_path = self.arguments.build_dir.clone().join("tags");
 
-        // Render the index page
-        let tags_index_rendered_html = tag_index_template
-            .render(context!(
-                base => &self.base_context
-            ))
-            .unwrap();
-        self.write_html_to_file(base_tags_path.join("index.html"), tags_index_rendered_html);
+        // Render the index page, which lists every tag
+        let mut index_fingerprint = self.template_fingerprint("tags.jinja");
+        index_fingerprint.add(&self.listings_hash);
+        self.write_if_changed(
+            base_tags_path.join("index.html"),
+            &index_fingerprint,
+            || {
+                tag_index_template
+                    .render(context!(
+                        base => &self.base_context
+                    ))
+                    .map_err(|e| SaaruError::template(&tags_template_path, "tags.jinja", e))
+            },
+        )?;
+
+        let tag_page_fingerprint = self.template_fingerprint("tags_page.jinja");
 
-        // Render a page for every single tag
+        // Render a page (or a set of pages) for every single tag
+        // /tags/<tag>.html, then /tags/<tag>/page/2/ and onwards
         for (key, val) in &self.tag_map {
-            let tags_index_rendered_html = tag_individual_template
-                .render(context!(
-                    tag => &key,
-                    posts => &val,
-                    base => &self.base_context
-                ))
-                .unwrap();
-            self.write_html_to_file(
-                base_tags_path.join(PathBuf::from(format!("{}.html", key))),
-                tags_index_rendered_html,
+            let pages = paginate(
+                val,
+                page_size(self.arguments.config.tags.page_size),
+                &format!("/tags/{}.html", key),
+                |page| format!("/tags/{}/page/{}/", key, page),
             );
+            for paginator in pages {
+                // A tag page only changes along with the posts on it
+                let mut fingerprint = tag_page_fingerprint.clone();
+                fingerprint.add(key).add(paginator_json(&paginator));
+                self.write_if_changed(paginator.write_path(), &fingerprint, || {
+                    tag_individual_template
+                        .render(context!(
+                            tag => &key,
+                            posts => &paginator.items,
+                            paginator => &paginator,
+                            base => &self.base_context
+                        ))
+                        .map_err(|e| {
+                            SaaruError::template(&tags_page_template_path, "tags_page.jinja", e)
+                        })
+                })?;
+            }
         }
+        Ok(())
     }
 
-    fn copy_static_folder(&self) {
+    fn render_collection_pages(&self) -> Result<()> {
+        // Generate /<collection>/, /<collection>/page/2/ and so on for every collection
+        let source_pages: Vec<&String> = self
+            .frontmatter_map
+            .values()
+            .map(|page| &page.write_path)
+            .collect();
+
+        for (collection, posts) in &self.collection_map {
+            let config = self
+                .arguments
+                .config
+                .collections
+                .get(collection)
+                .cloned()
+                .unwrap_or_default();
+            let template_name = config.template();
+            let collection_path = config.path(collection);
+
+            let template_path = self.arguments.template_dir.join(template_name);
+            let template = match self.template_env.get_template(template_name) {
+                Ok(template) => template,
+                // Sites that never set up a collection template just don't get index pages
+                Err(e)
+                    if e.kind() == minijinja::ErrorKind::TemplateNotFound
+                        && config.template.is_none() =>
+                {
+                    log::debug!(
+                        "No {:?} template, skipping index pages for collection {:?}",
+                        template_name,
+                        collection
+                    );
+                    continue;
+                }
+                Err(e) => return Err(SaaruError::template(&template_path, template_name, e)),
+            };
+
+            let pages = paginate(
+                posts,
+                config.page_size(),
+                &format!("/{}/", collection_path),
+                |page| format!("/{}/page/{}/", collection_path, page),
+            );
+            for paginator in pages {
+                let write_path = self.arguments.build_dir.join(paginator.write_path());
+                // A hand-written index page in the source directory wins over the generated one
+                if source_pages.contains(&&write_path.display().to_string()) {
+                    log::warn!(
+                        "{:?} already has a source page, not generating an index for collection {:?} there",
+                        write_path,
+                        collection
+                    );
+                    continue;
+                }
+
+                let mut fingerprint = self.template_fingerprint(template_name);
+                fingerprint.add(collection).add(paginator_json(&paginator));
+                self.write_if_changed(write_path, &fingerprint, || {
+                    template
+                        .render(context!(
+                            collection => &collection,
+                            paginator => &paginator,
+                            base => &self.base_context
+                        ))
+                        .map_err(|e| SaaruError::template(&template_path, template_name, e))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render_feeds(&self) -> Result<()> {
+        let (config, channel) = match &self.feeds {
+            Some(feeds) => feeds,
+            None => return Ok(()),
+        };
+
+        // The site-wide feed has every dated page
+        let site_posts: Vec<ThinAugmentedFrontMatter> = self
+            .frontmatter_map
+            .values()
+            .filter(|page| page.frontmatter.date.is_some())
+            .cloned()
+            .map(ThinAugmentedFrontMatter::from)
+            .collect();
+        self.write_feeds(config, channel, &channel.title, "", &site_posts)?;
+
+        for collection in &config.collections {
+            let posts = match self.collection_map.get(collection) {
+                Some(posts) => posts,
+                None => {
+                    log::warn!("No pages in collection {:?}, skipping its feed", collection);
+                    continue;
+                }
+            };
+            let directory = self
+                .arguments
+                .config
+                .collections
+                .get(collection)
+                .cloned()
+                .unwrap_or_default()
+                .path(collection);
+            let title = format!("{} - {}", channel.title, collection);
+            self.write_feeds(config, channel, &title, &directory, posts)?;
+        }
+
+        if config.tags {
+            for (tag, posts) in &self.tag_map {
+                let title = format!("{} - #{}", channel.title, tag);
+                self.write_feeds(config, channel, &title, &format!("tags/{}", tag), posts)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Write the RSS and/or Atom feed for one listing into `directory` (relative to the build dir)
+    fn write_feeds(
+        &self,
+        config: &FeedConfig,
+        channel: &FeedChannel,
+        title: &str,
+        directory: &str,
+        posts: &[ThinAugmentedFrontMatter],
+    ) -> Result<()> {
+        // Undated pages can't be placed in a feed, and feeds always read newest-first.
+        // Only the posts that make the cut get their markdown rendered.
+        let mut dated: Vec<&ThinAugmentedFrontMatter> = posts
+            .iter()
+            .filter(|post| post.frontmatter.date.is_some())
+            .collect();
+        dated.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
+        dated.truncate(config.limit);
+
+        let entries: Vec<FeedEntry> = dated
+            .into_iter()
+            .filter_map(|post| {
+                let date = post.frontmatter.date.clone()?;
+                let content = self
+                    .frontmatter_map
+                    .get(&post.source_path)
+                    .map(|page| self.convert_markdown_to_html(&page.file_content))
+                    .unwrap_or_default();
+                Some(FeedEntry {
+                    title: post.frontmatter.title.clone().unwrap_or_default(),
+                    description: post.frontmatter.description.clone().unwrap_or_default(),
+                    link: channel.absolute(&post.link),
+                    date,
+                    content,
+                })
+            })
+            .collect();
+
+        let feed_path = |file_name: &str| {
+            if directory.is_empty() {
+                file_name.to_string()
+            } else {
+                format!("{}/{}", directory, file_name)
+            }
+        };
+        if let Some(rss) = &config.rss {
+            let path = feed_path(rss);
+            let xml = render_rss(channel, title, &channel.absolute(&path), &entries);
+            self.write_html_to_file(PathBuf::from(path), xml)?;
+        }
+        if let Some(atom) = &config.atom {
+            let path = feed_path(atom);
+            let xml = render_atom(channel, title, &channel.absolute(&path), &entries);
+            self.write_html_to_file(PathBuf::from(path), xml)?;
+        }
+        Ok(())
+    }
+
+    // The page shown for URLs that don't exist, by the live reload server and most static hosts
+    fn render_not_found_page(&self) -> Result<()> {
+        // A hand-written 404 page in the source directory wins over the template
+        let output_path = self.arguments.build_dir.join(NOT_FOUND_PAGE);
+        if self
+            .frontmatter_map
+            .values()
+            .any(|page| Path::new(&page.write_path) == output_path)
+        {
+            return Ok(());
+        }
+
+        let templates = &self.arguments.config.metadata.templates;
+        let template_name = templates.not_found();
+        let template_path = self.arguments.template_dir.join(template_name);
+        // Sites without a 404 template don't get a 404 page, unless the config asks for one
+        if templates.not_found.is_none() && !template_path.is_file() {
+            log::debug!("No {} template, skipping {}", template_name, NOT_FOUND_PAGE);
+            return Ok(());
+        }
+        let template = self
+            .template_env
+            .get_template(template_name)
+            .map_err(|e| SaaruError::template(&template_path, template_name, e))?;
+
+        let fingerprint = self.template_fingerprint(template_name);
+        self.write_if_changed(PathBuf::from(NOT_FOUND_PAGE), &fingerprint, || {
+            template
+                .render(context!(base => &self.base_context))
+                .map_err(|e| SaaruError::template(&template_path, template_name, e))
+        })
+    }
+
+    fn render_sitemap(&self) -> Result<()> {
+        let sitemap = &self.arguments.config.sitemap;
+        let base_url = match self.arguments.config.metadata.base_url() {
+            Some(base_url) if sitemap.enabled => base_url,
+            _ => {
+                log::debug!("No metadata.base_url (or sitemap turned off), skipping sitemap.xml");
+                return Ok(());
+            }
+        };
+
+        let pages_by_output: HashMap<&str, &AugmentedFrontMatter> = self
+            .frontmatter_map
+            .values()
+            .map(|page| (page.write_path.as_str(), page))
+            .collect();
+
+        // Everything written so far is a page - rendered markdown, tag pages and
+        // collection index pages. Feeds aren't.
+        let written_files = self
+            .written_files
+            .lock()
+            .expect("written files lock poisoned")
+            .clone();
+        let mut entries: Vec<SitemapEntry> = Vec::new();
+        let not_found_page = self.arguments.build_dir.join(NOT_FOUND_PAGE);
+        for written in written_files {
+            // Neither is the 404 page
+            if written.extension().and_then(|ext| ext.to_str()) != Some("html")
+                || written == not_found_page
+            {
+                continue;
+            }
+            let link = self.get_relative_path_from_write_path(&written)?;
+            let link = link.to_string_lossy();
+
+            let lastmod = match pages_by_output.get(written.display().to_string().as_str()) {
+                Some(page) => {
+                    if page.frontmatter.is_draft() || page.frontmatter.sitemap == Some(false) {
+                        continue;
+                    }
+                    match &page.frontmatter.date {
+                        Some(date) => Some(date.to_rfc3339()),
+                        // No date, so go by when the source was last touched
+                        None => fs::metadata(&page.source_path)
+                            .and_then(|metadata| metadata.modified())
+                            .ok()
+                            .map(|modified| {
+                                chrono::DateTime::<chrono::Utc>::from(modified)
+                                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                            }),
+                    }
+                }
+                None => None,
+            };
+            entries.push(SitemapEntry {
+                loc: page_url(&base_url, &link),
+                lastmod,
+            });
+        }
+        entries.sort();
+        entries.dedup();
+
+        self.write_html_to_file(PathBuf::from("sitemap.xml"), render_sitemap(&entries))?;
+
+        if sitemap.robots_txt {
+            // A hand-written robots.txt in the static folder always wins
+            if self.arguments.static_dir.join("robots.txt").exists() {
+                log::warn!("static/robots.txt exists, not generating one");
+            } else {
+                let robots = render_robots_txt(&format!("{}/sitemap.xml", base_url));
+                self.write_html_to_file(PathBuf::from("robots.txt"), robots)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_static_folder(&self) -> Result<()> {
         // Copy over the static folder from the source directory to the
-        // build directory
+        // build directory, skipping files that are already there and haven't changed
         let source_path = &self.arguments.static_dir;
         let destination_path = &self.arguments.build_dir;
         log::info!(
@@ -407,173 +1405,580 @@ impl SaaruInstance {
             source_path,
             destination_path
         );
-        copy_recursively(source_path, destination_path).unwrap();
+
+        // A static file with the same path as a generated page has always won, so
+        // anything written over during this build has to be copied again
+        let written_files = self
+            .written_files
+            .lock()
+            .expect("written files lock poisoned")
+            .clone();
+        let mut cache = self.build_cache.lock().expect("build cache lock poisoned");
+        let mut static_files = HashMap::new();
+        let mut copied = 0;
+
+        for entry in WalkDir::new(source_path) {
+            let entry = entry.map_err(|e| {
+                let path = e.path().unwrap_or(source_path).to_path_buf();
+                SaaruError::io(path, e.into())
+            })?;
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            let relative = entry
+                .path()
+                .strip_prefix(source_path)
+                .expect("walkdir only yields paths inside the static folder");
+            let destination = destination_path.join(relative);
+            let key = relative.display().to_string();
+
+            let content = fs::read(entry.path()).map_err(|e| SaaruError::io(entry.path(), e))?;
+            let hash = hash_bytes(&content);
+            let disk_path = self.output_path(&destination);
+            let unchanged = disk_path.is_file()
+                && !written_files.contains(&destination)
+                && cache.static_files.get(&key) == Some(&hash);
+            if let Some(dry_run) = &self.dry_run {
+                dry_run
+                    .lock()
+                    .expect("dry run lock poisoned")
+                    .record(destination, None);
+            } else if !unchanged {
+                if let Some(parent) = disk_path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| SaaruError::io(parent, e))?;
+                }
+                replace_file(&disk_path, |temporary| {
+                    fs::copy(entry.path(), temporary).map(|_| ())
+                })
+                .map_err(|e| SaaruError::io(entry.path(), e))?;
+                copied += 1;
+            }
+            static_files.insert(key, hash);
+        }
+
+        log::info!(
+            "Copied {} new or changed static files ({} in total)",
+            copied,
+            static_files.len()
+        );
+        if self.dry_run.is_none() {
+            cache.static_files = static_files;
+        }
+        self.counters.copied.store(copied, Ordering::Relaxed);
+        Ok(())
     }
 
-    pub fn render_pipeline(&mut self) {
+    // Sum up the last full build
+    pub fn build_report(&self, duration: time::Duration) -> BuildReport {
+        BuildReport {
+            build_dir: self.arguments.build_dir.clone(),
+            pages: self.frontmatter_map.len(),
+            outputs: self
+                .written_files
+                .lock()
+                .expect("written files lock poisoned")
+                .len(),
+            rendered: self.counters.rendered.load(Ordering::Relaxed),
+            static_files: self
+                .build_cache
+                .lock()
+                .expect("build cache lock poisoned")
+                .static_files
+                .len(),
+            static_copied: self.counters.copied.load(Ordering::Relaxed),
+            pruned: self.counters.pruned.load(Ordering::Relaxed),
+            duration,
+        }
+    }
+
+    pub fn render_pipeline(&mut self) -> Result<()> {
         // Full pipeline for rendering again
         // Stage 0: Validate the submitted folder structur
         // Stage 1: Preprocess all files, make all necessary directories
         // Stage 2: Render everything from the preprocessed map
+        log::info!("Building site at {:?}", self.arguments.base_dir);
 
         log::debug!("[PREFLIGHT] Validating Input Directory");
-        if !self.validate_source_structure() {
-            panic!("The Provided Source Directory is malformed! Please follow the right format.")
+        self.validate_source_structure()?;
+        self.counters = BuildCounters::default();
+        // minijinja keeps every template it has loaded, including ones that have been
+        // fixed since, like after a failed live rebuild. The graph is rescanned below.
+        self.set_template_environment();
+
+        // Only the first build starts from an empty directory, rebuilds in live mode prune instead
+        let clean = std::mem::take(&mut self.arguments.clean);
+        let staging = self.prepare_staging(clean)?;
+        self.output_dir = staging.clone();
+
+        // Put back if the build fails, since the build directory stays as it was
+        let previous_files = std::mem::take(
+            &mut *self
+                .written_files
+                .lock()
+                .expect("written files lock poisoned"),
+        );
+        let previous_cache = self
+            .build_cache
+            .lock()
+            .expect("build cache lock poisoned")
+            .clone();
+
+        // Start from a clean slate, this may not be the first build of this instance
+        log::debug!("Invalidating Collections, Tags and Frontmatter Maps");
+        self.collection_map = HashMap::new();
+        self.tag_map = HashMap::new();
+        self.frontmatter_map = HashMap::new();
+
+        let built = self
+            .render_stages()
+            .and_then(|_| self.prune_stale_outputs());
+        self.output_dir = self.arguments.build_dir.clone();
+
+        match built {
+            Ok(()) => {
+                self.swap_in_staging(&staging)?;
+                self.save_build_cache(true)
+            }
+            Err(e) => {
+                // Nobody gets to see half a site, so throw away everything this build wrote
+                log::warn!(
+                    "Build failed, keeping the previous build in {:?}",
+                    self.arguments.build_dir
+                );
+                if let Err(e) = remove_dir_if_exists(&staging) {
+                    log::warn!("Couldn't remove the staging directory {:?}: {}", staging, e);
+                }
+                *self
+                    .written_files
+                    .lock()
+                    .expect("written files lock poisoned") = previous_files;
+                *self.build_cache.lock().expect("build cache lock poisoned") = previous_cache;
+                Err(e)
+            }
         }
+    }
 
-        log::debug!("[PREFLIGHT] Checking for Build Directory");
-        match fs::create_dir(&self.arguments.build_dir) {
-            Ok(_) => log::info!("Build Directory Created Successfully"),
-            Err(_) => log::warn!("Build Directory Already Exists!"),
-        };
+    // Go through the whole site the way a build would, but keep every output in memory
+    // instead of writing it, and report everything that's wrong with it
+    pub fn check(&mut self) -> Result<CheckReport> {
+        log::info!("Checking site at {:?}", self.arguments.base_dir);
+        self.validate_source_structure()?;
+
+        // Every problem is worth knowing about, not just the first one. Both of these
+        // go back to how they were afterwards, for the next build of this instance.
+        let keep_going = std::mem::replace(&mut self.arguments.keep_going, true);
+        let previous_files = std::mem::take(
+            &mut *self
+                .written_files
+                .lock()
+                .expect("written files lock poisoned"),
+        );
+        self.dry_run = Some(Mutex::new(DryRun::default()));
+        self.collection_map = HashMap::new();
+        self.tag_map = HashMap::new();
+        self.frontmatter_map = HashMap::new();
+
+        let mut problems = Vec::new();
+        match self.render_stages() {
+            Ok(()) => {}
+            Err(SaaruError::Build { errors }) => {
+                problems.extend(errors.into_iter().map(Problem::Build))
+            }
+            Err(e) => problems.push(Problem::Build(e)),
+        }
+        self.arguments.keep_going = keep_going;
+        let outputs = std::mem::replace(
+            &mut *self
+                .written_files
+                .lock()
+                .expect("written files lock poisoned"),
+            previous_files,
+        )
+        .len();
+        let dry_run = self
+            .dry_run
+            .take()
+            .expect("dry run set above")
+            .into_inner()
+            .expect("dry run lock poisoned");
+
+        let arguments = &self.arguments;
+        problems.extend(duplicate_outputs(
+            &dry_run,
+            &self.frontmatter_map,
+            &arguments.build_dir,
+            &arguments.static_dir,
+        ));
+        for (template, collection) in self.template_graph.referenced_collections() {
+            if !self.collection_map.contains_key(collection) {
+                problems.push(Problem::UnknownCollection {
+                    template: arguments.template_dir.join(template),
+                    collection: collection.to_string(),
+                });
+            }
+        }
+        problems.extend(broken_links(
+            &dry_run,
+            &self.frontmatter_map,
+            &arguments.build_dir,
+            &arguments.config.server_prefix(),
+        ));
+
+        Ok(CheckReport {
+            pages: self.frontmatter_map.len(),
+            outputs,
+            problems,
+        })
+    }
+
+    fn render_stages(&mut self) -> Result<()> {
+        let keep_going = self.arguments.keep_going;
+        let mut errors: Vec<SaaruError> = Vec::new();
 
         log::debug!("[LOG] Recursively Preprocessing All Files");
         for dir in WalkDir::new(&self.arguments.source_dir) {
-            let entry = dir.unwrap();
+            let entry = dir.map_err(|e| {
+                let path = e.path().unwrap_or(&self.arguments.source_dir).to_path_buf();
+                SaaruError::io(path, e.into())
+            })?;
             let local_path = entry.path();
-            let metadata = fs::metadata(&local_path).unwrap();
-            if metadata.is_dir()
-                || local_path.extension().unwrap().to_str().unwrap() != &"md".to_string()
+            if entry.file_type().is_dir()
+                || local_path.extension().and_then(|ext| ext.to_str()) != Some("md")
             {
                 continue;
             }
             log::debug!("Processing File {:?}", entry);
-            self.preprocess_file_data(entry.path());
+            collect_error(
+                keep_going,
+                &mut errors,
+                self.preprocess_file_data(entry.path()),
+            )?;
             log::debug!("Finished Processing File {:?}", entry);
         }
 
-        log::debug!("Generating DDM Context...");
-        self.base_context = context!(
-            tags => &self.tag_map,
-            collections => &self.collection_map,
-            json => &self.arguments.json_content
-        );
+        log::debug!("Sorting Tags and Collections...");
+        self.sort_listings();
+
+        self.refresh_context();
+        self.build_fingerprint = self.compute_build_fingerprint();
+        self.template_graph = TemplateGraph::scan(&self.arguments.template_dir)?;
 
         log::info!("Rendering All Files...");
-        self.render_all_files();
+        if keep_going {
+            for e in self.render_frontmatter_map() {
+                collect_error(keep_going, &mut errors, Err(e))?;
+            }
+        } else {
+            self.render_all_files()?;
+        }
         log::info!("Rendering Tags");
-        self.render_tags_pages();
+        collect_error(keep_going, &mut errors, self.render_tags_pages())?;
+        log::info!("Rendering Collections");
+        collect_error(keep_going, &mut errors, self.render_collection_pages())?;
+        log::info!("Rendering the 404 Page");
+        collect_error(keep_going, &mut errors, self.render_not_found_page())?;
+        log::info!("Rendering Feeds");
+        collect_error(keep_going, &mut errors, self.render_feeds())?;
+        log::info!("Rendering Sitemap");
+        collect_error(keep_going, &mut errors, self.render_sitemap())?;
         log::info!("Copying the static folder... ");
-        self.copy_static_folder();
+        collect_error(keep_going, &mut errors, self.copy_static_folder())?;
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SaaruError::Build { errors })
+        }
+    }
+
+    // Start over with a fresh template environment and dependency graph after templates changed.
+    // The template hashes are part of every page's fingerprint, so the cache then picks out
+    // exactly the pages that render through them.
+    fn reload_templates(&mut self) -> Result<()> {
+        // minijinja keeps every template it has loaded, so start over with a fresh environment
+        self.set_template_environment();
+        self.template_graph = TemplateGraph::scan(&self.arguments.template_dir)?;
+        Ok(())
+    }
+
+    // Pick up an edited config file, then rebuild everything, since every page sees it as `base.json`
+    fn reload_config(&mut self) -> Result<()> {
+        self.arguments.reload_config()?;
+        let config = &self.arguments.config;
+        self.markdown_options = markdown_options(&config.markdown);
+        self.default_template = config.metadata.templates.default.clone();
+        self.parallel_render_threads = config.threads;
+        self.feeds = feeds(&self.arguments)?;
+
+        self.set_template_environment();
+        self.render_pipeline()
     }
 
-    pub fn orchestrator(mut self) {
+    // Apply everything that changed on disk while live re-rendering, then render what it
+    // affects in one go. A broken file doesn't stop the rest of the batch from going through.
+    fn apply_changes(&mut self, paths: &BTreeSet<PathBuf>) -> Result<()> {
+        let config_path = self.arguments.config_path.clone();
+        if paths.contains(&config_path) && config_path.exists() {
+            log::info!("Config Changed. Reloading it and re-rendering entire website.");
+            self.reload_config()?;
+            return self.save_build_cache(false);
+        }
+
+        let mut errors = Vec::new();
+        let mut pages_changed = false;
+        let mut templates_changed = false;
+        let mut static_changed = false;
+        // Whether a path was created, changed or removed is only worked out now that things
+        // have settled, since platforms report creations, deletions and renames differently
+        for path in paths {
+            let exists = path.exists();
+            if *path == config_path {
+                log::warn!("Config file removed, carrying on with the last one that was read");
+            } else if path.starts_with(&self.arguments.source_dir) {
+                if !exists {
+                    pages_changed |= self.remove_source_path(path);
+                } else if path.is_dir() {
+                    match self.load_new_directory(path) {
+                        Ok(()) => pages_changed = true,
+                        Err(e) => errors.push(e),
+                    }
+                } else if path.extension().and_then(|ext| ext.to_str()) == Some("md") {
+                    match self.reload_page(path) {
+                        Ok(()) => pages_changed = true,
+                        Err(e) => errors.push(e),
+                    }
+                }
+            } else if path.starts_with(&self.arguments.template_dir) {
+                templates_changed = true;
+            } else if path.starts_with(&self.arguments.static_dir) {
+                if exists {
+                    static_changed = true;
+                } else if let Err(e) = self.remove_static_path(path) {
+                    errors.push(e);
+                }
+            }
+        }
+
+        if templates_changed {
+            log::info!("Templates Changed. Re-rendering the pages that use them");
+            match self.reload_templates() {
+                Ok(()) => pages_changed = true,
+                Err(e) => errors.push(e),
+            }
+        }
+        if pages_changed {
+            if let Err(e) = self.render_changed_pages() {
+                errors.push(e);
+            }
+        }
+        if static_changed {
+            log::info!("Static Files Changed. Recopying static folder");
+            if let Err(e) = self.copy_static_folder() {
+                errors.push(e);
+            }
+        }
+        if let Err(e) = self.save_build_cache(false) {
+            errors.push(e);
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(SaaruError::Build { errors }),
+        }
+    }
+
+    // `failed_build` is why the build before live reload started failed, if it did
+    pub fn orchestrator(mut self, failed_build: Option<SaaruError>) -> Result<()> {
         // Launch Point for the Saaru Orchestrator
         log::info!("starting the orchestrator");
         let (tx, rx) = unbounded::<SaaruEvent>();
 
+        // Shared with the web server, which puts it on top of every page
+        let overlay: ErrorOverlay = Arc::new(Mutex::new(failed_build.as_ref().map(render_overlay)));
+        let server_overlay = overlay.clone();
+        // A failed rebuild may have left things half-done, so the next one starts from scratch
+        let mut needs_full_build = failed_build.is_some();
+
         // Make Separate Watch Dirs for each reloadable segment
         let watch_dir = self.arguments.source_dir.as_path();
         let static_watch_dir = self.arguments.static_dir.as_path();
-        // let template_watch_dir = self.arguments.template_dir.as_path().clone();
+        let template_watch_dir = self.arguments.template_dir.as_path();
+        // Editors often save by replacing the file, so watch the directory the config lives in
+        let config_path = self.arguments.config_path.clone();
+        let config_watch_dir = config_path
+            .parent()
+            .unwrap_or(&self.arguments.base_dir)
+            .to_path_buf();
 
         // Initialize Reloader
         let reload_layer = LiveReloadLayer::new();
         let reloader = reload_layer.reloader();
 
-        let live_reload = self.arguments.live_reload.clone();
-        let _live_rerender = self.arguments.live_rerender.clone();
+        let live_reload = self.arguments.live_reload;
+        let _live_rerender = self.arguments.live_rerender;
 
         let watcher_sender = tx.clone();
         let static_watcher_sender = tx.clone();
-        // let template_watcher_sender = tx.clone();
+        let template_watcher_sender = tx.clone();
+        let config_watcher_sender = tx.clone();
 
         let build_dir = self.arguments.build_dir.clone();
+        let server = self.arguments.config.server.clone();
+        let prefix = self.arguments.config.server_prefix();
+
+        // Bind before anything starts, so a server that can't start stops everything
+        let listener = if live_reload {
+            let listener = bind_listener(&server.address, server.port).map_err(|e| {
+                SaaruError::config(
+                    &config_path,
+                    format!(
+                        "couldn't start the web server on {}:{}: {}",
+                        server.address, server.port, e
+                    ),
+                )
+            })?;
+            Some(listener)
+        } else {
+            None
+        };
+        let server_config_path = config_path.clone();
 
         // Setup the watcher (somehow works on a parallel thread?)
         let mut content_watcher = RecommendedWatcher::new(
-            move |res: Result<Event, Error>| {
-                watcher_sender.send(SaaruEvent::FileChanged(res)).unwrap();
+            move |res: notify::Result<Event>| {
+                // The listener only goes away when the process is shutting down
+                let _ = watcher_sender.send(SaaruEvent::FileChanged(res));
             },
             Config::default(),
         )
-        .unwrap();
+        .map_err(|e| SaaruError::Watch {
+            path: watch_dir.to_path_buf(),
+            source: e,
+        })?;
         content_watcher
             .watch(watch_dir, RecursiveMode::Recursive)
-            .unwrap();
+            .map_err(|e| SaaruError::Watch {
+                path: watch_dir.to_path_buf(),
+                source: e,
+            })?;
 
         // Staticfile watcher
         let mut static_watcher = RecommendedWatcher::new(
-            move |res: Result<Event, Error>| {
-                static_watcher_sender
-                    .send(SaaruEvent::FileChanged(res))
-                    .unwrap();
+            move |res: notify::Result<Event>| {
+                let _ = static_watcher_sender.send(SaaruEvent::FileChanged(res));
             },
             Config::default(),
         )
-        .unwrap();
+        .map_err(|e| SaaruError::Watch {
+            path: static_watch_dir.to_path_buf(),
+            source: e,
+        })?;
         static_watcher
             .watch(static_watch_dir, RecursiveMode::Recursive)
-            .unwrap();
+            .map_err(|e| SaaruError::Watch {
+                path: static_watch_dir.to_path_buf(),
+                source: e,
+            })?;
+
+        // Template watcher
+        let mut template_watcher = RecommendedWatcher::new(
+            move |res: notify::Result<Event>| {
+                let _ = template_watcher_sender.send(SaaruEvent::FileChanged(res));
+            },
+            Config::default(),
+        )
+        .map_err(|e| SaaruError::Watch {
+            path: template_watch_dir.to_path_buf(),
+            source: e,
+        })?;
+        template_watcher
+            .watch(template_watch_dir, RecursiveMode::Recursive)
+            .map_err(|e| SaaruError::Watch {
+                path: template_watch_dir.to_path_buf(),
+                source: e,
+            })?;
+
+        // Config watcher, only passing on events for the config file itself
+        let mut config_watcher = RecommendedWatcher::new(
+            move |res: notify::Result<Event>| {
+                let is_config = match &res {
+                    Ok(event) => event.paths.contains(&config_path),
+                    Err(_) => true,
+                };
+                if is_config {
+                    let _ = config_watcher_sender.send(SaaruEvent::FileChanged(res));
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| SaaruError::Watch {
+            path: config_watch_dir.clone(),
+            source: e,
+        })?;
+        config_watcher
+            .watch(&config_watch_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| SaaruError::Watch {
+                path: config_watch_dir.clone(),
+                source: e,
+            })?;
 
         // Setup the listener (and now, orchestrator)
         let listener_thread = std::thread::spawn(move || {
             let listener = rx.clone();
             let sender = tx.clone();
             // Listen to all the events on the wire
-            for x in listener {
+            for x in listener.iter() {
                 match x {
-                    SaaruEvent::FileChanged(filechangeevent) => match filechangeevent {
-                        Ok(event) => {
-                            match event.kind {
-                                notify::EventKind::Modify(ModifyKind::Data(_))
-                                | notify::EventKind::Access(AccessKind::Close(_)) => {
-                                    log::info!(
-                                        "[LIVERELOAD] Re-processing file {:?}",
-                                        &event.paths[0]
-                                    );
-                                    let start = time::Instant::now();
-
-                                    // TODO Check if it's a static file, if so, copy over
-                                    match &event.paths[0].extension().unwrap().to_str().unwrap() {
-                                        &"md" => {
-                                            log::info!("Changed Markdown File -> Re-rendering individual file");
-                                            self.borrow_mut()
-                                                .render_individual_file(&event.paths[0]);
-                                        }
-                                        _ => {
-                                            // Check if the re-render is from the static files
-                                            // or if it's a template
-
-                                            if &event.paths[0]
-                                                .starts_with(&self.arguments.static_dir)
-                                                == &true
-                                            {
-                                                log::info!("Static File Changed. Skipping re-render, recopying static folder");
-                                                // Copy static folder again
-                                                self.borrow_mut().copy_static_folder();
-                                            } else {
-                                                // Something
-                                                log::info!("Non-Static File Changed. Re-Rendering entire website.");
-
-                                                // Invalidate the frontmatter
-                                                log::warn!("Invalidating Collections Map");
-                                                self.borrow_mut().collection_map = HashMap::new();
-                                                log::warn!("Invalidating Tag Map");
-                                                self.borrow_mut().tag_map = HashMap::new();
-                                                log::warn!("Invalidating Frontmatter Map");
-                                                self.borrow_mut().frontmatter_map = HashMap::new();
-                                                log::info!("Triggering Full Site Re-Render");
-                                                self.borrow_mut().render_pipeline();
-                                            }
-                                        }
-                                    }
-
-                                    let end = time::Instant::now();
-                                    log::info!(
-                                        "File {:?} re-rendered in {:?}",
-                                        event.paths[0],
-                                        end - start
-                                    );
-                                    sender.send(SaaruEvent::FileReRenderCompleted).unwrap();
+                    SaaruEvent::FileChanged(first) => {
+                        // A single save is often several events (and editors touch more than
+                        // one file), so wait until things go quiet and handle them all at once
+                        let mut paths = BTreeSet::new();
+                        collect_touched_paths(first, &mut paths);
+                        loop {
+                            match listener.recv_timeout(DEBOUNCE) {
+                                Ok(SaaruEvent::FileChanged(event)) => {
+                                    collect_touched_paths(event, &mut paths)
                                 }
-                                _ => {}
+                                Ok(SaaruEvent::FileReRenderCompleted) => reloader.reload(),
+                                Err(RecvTimeoutError::Timeout) => break,
+                                Err(RecvTimeoutError::Disconnected) => break,
                             }
                         }
-                        Err(e) => log::error!("[LIVERELOAD] {:?}", e),
-                    },
+                        if paths.is_empty() {
+                            continue;
+                        }
+
+                        log::info!("[LIVERELOAD] Re-processing {:?}", &paths);
+                        let start = time::Instant::now();
+                        let rebuilt = if needs_full_build {
+                            self.render_pipeline()
+                        } else {
+                            self.apply_changes(&paths)
+                        };
+                        // A broken file shouldn't take the watcher down with it, report it
+                        // (in the browser too) and wait for the next save
+                        let shown = match rebuilt {
+                            Ok(()) => {
+                                let end = time::Instant::now();
+                                log::info!(
+                                    "{} changes re-rendered in {:?}",
+                                    paths.len(),
+                                    end - start
+                                );
+                                needs_full_build = false;
+                                None
+                            }
+                            Err(e) => {
+                                log::error!("[LIVERELOAD] {}", e);
+                                needs_full_build = true;
+                                Some(render_overlay(&e))
+                            }
+                        };
+                        *overlay.lock().expect("overlay lock poisoned") = shown;
+                        let _ = sender.send(SaaruEvent::FileReRenderCompleted);
+                    }
                     SaaruEvent::FileReRenderCompleted => {
-                        // TODO implment the reload signal
                         log::info!("Recieved Re-Render Completion Event");
                         reloader.reload();
                     }
@@ -581,35 +1986,258 @@ impl SaaruInstance {
             }
         });
 
-        if live_reload {
+        if let Some(listener) = listener {
             let server_thread = thread::spawn(move || {
                 // Initialize Web Server
-                let app = Router::new()
-                    .nest_service("/", serve_dir(&build_dir))
+                let site = serve_dir(&build_dir, &prefix, server_overlay);
+                let router = if prefix.is_empty() {
+                    Router::new().nest_service("/", site)
+                } else {
+                    // Send anyone asking for the root to where the site actually is
+                    let site_root = format!("{}/", prefix);
+                    Router::new().nest_service(&prefix, site).route(
+                        "/",
+                        get(move || async move { Redirect::temporary(&site_root) }),
+                    )
+                };
+                let app = router
                     .layer(reload_layer)
                     .layer(no_cache_layer())
                     .into_make_service();
 
                 // start the web server
-                let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 3000);
-                start_server(&addr, app).unwrap();
+                start_server(listener, &prefix, app).map_err(|e| {
+                    format!(
+                        "web server on {}:{} stopped: {}",
+                        server.address, server.port, e
+                    )
+                })
             });
 
-            server_thread.join().unwrap();
+            // The server only comes back when it has failed, which is the end of live reload
+            if let Err(message) = server_thread.join().expect("web server thread panicked") {
+                return Err(SaaruError::config(&server_config_path, message));
+            }
+        }
+        listener_thread
+            .join()
+            .expect("orchestrator thread panicked");
+        Ok(())
+    }
+}
+
+// Add the paths an event touches to `paths`, leaving out editor scratch files
+fn collect_touched_paths(event: notify::Result<Event>, paths: &mut BTreeSet<PathBuf>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            log::error!("[LIVERELOAD] {:?}", e);
+            return;
+        }
+    };
+    match event.kind {
+        // inotify sends this on top of separate events for the old and the new name
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {}
+        EventKind::Modify(ModifyKind::Data(_))
+        | EventKind::Modify(ModifyKind::Name(_))
+        | EventKind::Access(AccessKind::Close(_))
+        | EventKind::Create(_)
+        | EventKind::Remove(_) => paths.extend(
+            event
+                .paths
+                .into_iter()
+                .filter(|path| !is_editor_temp_file(path)),
+        ),
+        _ => {}
+    }
+}
+
+// Everything a listing page shows, for its fingerprint
+fn paginator_json(paginator: &Paginator) -> String {
+    serde_json::to_string(paginator).expect("paginators always serialize")
+}
+
+// Turn the `markdown` section of the config into comrak's options
+fn markdown_options(markdown: &MarkdownConfig) -> ComrakOptions {
+    let mut options = ComrakOptions::default();
+    options.extension.front_matter_delimiter = Some("---".to_owned());
+
+    // Allow raw HTML!
+    options.render.unsafe_ = markdown.unsafe_html;
+    options.extension.table = markdown.tables;
+    options.extension.autolink = markdown.autolink;
+    options.extension.tasklist = markdown.tasklists;
+    options.extension.footnotes = markdown.footnotes;
+    options.extension.strikethrough = markdown.strikethrough;
+    options.extension.description_lists = markdown.description_lists;
+    options.extension.superscript = markdown.superscript;
+    options.extension.tagfilter = markdown.tagfilter;
+    options
+}
+
+// Which feeds to generate, if any, along with the site details that go in them
+fn feeds(args: &SaaruArguments) -> Result<Option<(FeedConfig, FeedChannel)>> {
+    match &args.config.feeds {
+        Some(feeds) => Ok(Some((
+            feeds.clone(),
+            FeedChannel::from_metadata(&args.config.metadata, &args.config_path)?,
+        ))),
+        None => Ok(None),
+    }
+}
+
+// In --keep-going mode, stash the error and carry on; otherwise bail out right away
+fn collect_error(keep_going: bool, errors: &mut Vec<SaaruError>, result: Result<()>) -> Result<()> {
+    match result {
+        Err(e) if keep_going => {
+            log::debug!("Continuing past error: {}", e);
+            errors.push(e);
+            Ok(())
         }
-        listener_thread.join().unwrap();
+        other => other,
     }
 }
 
 // Attrib - https://github.com/leotaku/tower-livereload/blob/master/examples/livehttpd/src/main.rs
-fn serve_dir(path: &std::path::Path) -> axum::routing::MethodRouter {
-    get_service(ServeDir::new(path)).handle_error(|error| async move {
-        (
-            http::StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Unhandled internal error: {}", error),
-        )
+// Serve the build directory the way static hosts do: `/posts/hello` finds `posts/hello.html`,
+// `dir/` finds `dir/index.html`, and anything missing gets the site's own 404 page
+// `prefix` is where the site is nested in the router, which redirects have to keep
+fn serve_dir(
+    path: &std::path::Path,
+    prefix: &str,
+    overlay: ErrorOverlay,
+) -> axum::routing::MethodRouter {
+    let build_dir = path.to_path_buf();
+    let prefix = prefix.to_string();
+    get_service(service_fn(move |request: Request<Body>| {
+        let build_dir = build_dir.clone();
+        let prefix = prefix.clone();
+        let overlay = overlay.clone();
+        async move { Ok::<_, Infallible>(serve_site(&build_dir, &prefix, &overlay, request).await) }
+    }))
+}
+
+async fn serve_site(
+    build_dir: &Path,
+    prefix: &str,
+    overlay: &ErrorOverlay,
+    mut request: Request<Body>,
+) -> Response {
+    if let Some(rewritten) = clean_url(build_dir, request.uri()) {
+        *request.uri_mut() = rewritten;
+    }
+    // `ServeDir` would redirect `/posts` to `/posts/` too, but without the prefix
+    if let Some(location) = directory_redirect(build_dir, prefix, request.uri()) {
+        return Redirect::temporary(&location).into_response();
+    }
+    let response = match ServeDir::new(build_dir).oneshot(request).await {
+        Ok(response) => response.map(boxed),
+        Err(error) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unhandled internal error: {}", error),
+            )
+                .into_response()
+        }
+    };
+    let response = if response.status() == StatusCode::NOT_FOUND {
+        match tokio::fs::read(build_dir.join(NOT_FOUND_PAGE)).await {
+            Ok(page) => (StatusCode::NOT_FOUND, Html(page)).into_response(),
+            Err(_) => response,
+        }
+    } else {
+        response
+    };
+
+    let overlay = overlay.lock().expect("overlay lock poisoned").clone();
+    match overlay {
+        Some(overlay) => with_overlay(response, &overlay).await,
+        None => response,
+    }
+}
+
+// Show the error overlay on top of an HTML page, or on its own instead of a bare 404
+async fn with_overlay(response: Response, overlay: &str) -> Response {
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    if !is_html {
+        if response.status() == StatusCode::NOT_FOUND {
+            return (StatusCode::NOT_FOUND, Html(overlay_page(overlay))).into_response();
+        }
+        return response;
+    }
+
+    let (mut parts, mut body) = response.into_parts();
+    let mut page = Vec::new();
+    while let Some(chunk) = body.data().await {
+        match chunk {
+            Ok(chunk) => page.extend_from_slice(&chunk),
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unhandled internal error: {}", e),
+                )
+                    .into_response()
+            }
+        }
+    }
+    // The page just got longer
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, boxed(Full::from(inject_overlay(&page, overlay))))
+}
+
+// The path a request asks for inside the build directory, unless it's the root, ends
+// in a slash, or tries to climb out (which is left to `ServeDir` to turn down)
+fn requested_path(uri: &Uri) -> Option<String> {
+    let decoded = percent_decode_str(uri.path()).decode_utf8().ok()?;
+    let relative = decoded.trim_start_matches('/');
+    if relative.is_empty() || relative.ends_with('/') {
+        return None;
+    }
+    if !Path::new(relative)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(relative.to_string())
+}
+
+// `/posts/hello` -> `/posts/hello.html`, if that's a file and `posts/hello` itself can't be
+// served: it doesn't exist, or it's a directory without an `index.html` (like the one
+// tag feeds and paginated tag pages go in, next to `tags/<tag>.html`)
+fn clean_url(build_dir: &Path, uri: &Uri) -> Option<Uri> {
+    let relative = requested_path(uri)?;
+    let target = build_dir.join(&relative);
+    if target.is_file()
+        || target.join("index.html").is_file()
+        || !build_dir.join(format!("{}.html", relative)).is_file()
+    {
+        return None;
+    }
+    let path = uri.path();
+    let rewritten = match uri.query() {
+        Some(query) => format!("{}.html?{}", path, query),
+        None => format!("{}.html", path),
+    };
+    rewritten.parse().ok()
+}
+
+// `/posts` -> `<prefix>/posts/`, when `posts` is a directory
+fn directory_redirect(build_dir: &Path, prefix: &str, uri: &Uri) -> Option<String> {
+    let relative = requested_path(uri)?;
+    if !build_dir.join(relative).is_dir() {
+        return None;
+    }
+    Some(match uri.query() {
+        Some(query) => format!("{}{}/?{}", prefix, uri.path(), query),
+        None => format!("{}{}/", prefix, uri.path()),
     })
 }
+
 type Srhl = SetResponseHeaderLayer<http::HeaderValue>;
 
 fn no_cache_layer() -> Stack<Srhl, Stack<Srhl, Srhl>> {
@@ -631,12 +2259,36 @@ fn no_cache_layer() -> Stack<Srhl, Stack<Srhl, Srhl>> {
     )
 }
 
+// Bind to the configured port, or the next free one after it when something else has it
+fn bind_listener(address: &str, port: u16) -> std::io::Result<TcpListener> {
+    let mut candidate = port;
+    loop {
+        match TcpListener::bind((address, candidate)) {
+            Ok(listener) => return Ok(listener),
+            Err(e)
+                if e.kind() == std::io::ErrorKind::AddrInUse
+                    && candidate < port.saturating_add(PORT_ATTEMPTS)
+                    && candidate < u16::MAX =>
+            {
+                log::warn!("Port {} is taken, trying {}", candidate, candidate + 1);
+                candidate += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
 #[tokio::main]
 async fn start_server(
-    addr: &SocketAddr,
+    listener: TcpListener,
+    prefix: &str,
     app: IntoMakeService<Router>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    log::info!("Serving on: http://{}/", addr);
-    axum::Server::try_bind(&addr)?.serve(app).await?;
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let addr = listener.local_addr()?;
+    log::info!("Serving on: http://{}{}/", addr, prefix);
+    if addr.ip().is_unspecified() {
+        log::info!("Listening on every network interface, use this machine's address to connect from other devices");
+    }
+    axum::Server::from_tcp(listener)?.serve(app).await?;
     Ok(())
 }
@@ -0,0 +1,166 @@
+use chrono::{Local, SecondsFormat};
+use gray_matter::{engine::YAML, Matter};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::arguments::SaaruArguments;
+use crate::error::{Result, SaaruError};
+use crate::frontmatter::FrontMatter;
+use crate::utils::resolve_path;
+
+// The files of a new site, relative to its directory. They're embedded in the
+// binary, so `saaru new site` works without a copy of this repository.
+const SKELETON: &[(&str, &str)] = &[
+    (".saaru.json", include_str!("../skeleton/.saaru.json")),
+    ("src/index.md", include_str!("../skeleton/src/index.md")),
+    (
+        "src/posts/hello-world.md",
+        include_str!("../skeleton/src/posts/hello-world.md"),
+    ),
+    (
+        "templates/base.jinja",
+        include_str!("../skeleton/templates/base.jinja"),
+    ),
+    (
+        "templates/index.jinja",
+        include_str!("../skeleton/templates/index.jinja"),
+    ),
+    (
+        "templates/post.jinja",
+        include_str!("../skeleton/templates/post.jinja"),
+    ),
+    (
+        "templates/tags.jinja",
+        include_str!("../skeleton/templates/tags.jinja"),
+    ),
+    (
+        "templates/tags_page.jinja",
+        include_str!("../skeleton/templates/tags_page.jinja"),
+    ),
+    (
+        "templates/collection.jinja",
+        include_str!("../skeleton/templates/collection.jinja"),
+    ),
+    (
+        "templates/404.jinja",
+        include_str!("../skeleton/templates/404.jinja"),
+    ),
+    (
+        "static/style.css",
+        include_str!("../skeleton/static/style.css"),
+    ),
+];
+
+/// Write a working site into `directory`, which must be new or empty.
+/// Returns the files it wrote.
+pub fn new_site(directory: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let directory = directory.as_ref();
+    if directory.exists() {
+        let mut entries = fs::read_dir(directory).map_err(|e| SaaruError::io(directory, e))?;
+        if entries.next().is_some() {
+            return Err(SaaruError::config(
+                directory,
+                "already exists and isn't empty, pick a new directory for the site",
+            ));
+        }
+    }
+
+    let mut written = Vec::new();
+    for (relative_path, content) in SKELETON {
+        let path = directory.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| SaaruError::io(parent, e))?;
+        }
+        fs::write(&path, content).map_err(|e| SaaruError::io(&path, e))?;
+        log::info!("Created {:?}", &path);
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// What goes into the frontmatter of a new post
+#[derive(Debug, Clone, Default)]
+pub struct NewPost {
+    pub title: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub collections: Vec<String>,
+    /// Defaults to the site's default template
+    pub template: Option<String>,
+    /// Mark the post `wip: true`, so it's left out of builds without --drafts
+    pub draft: bool,
+}
+
+// The frontmatter fields a new post starts with, in the order they're written out
+#[derive(Serialize)]
+struct NewPostFrontMatter<'a> {
+    title: &'a str,
+    description: &'a str,
+    date: String,
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    collections: &'a [String],
+    template: &'a str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    wip: bool,
+}
+
+// Create `path` (relative to the site's source directory, `.md` added if it has no
+// extension) with frontmatter for `post`. Existing files are never overwritten.
+pub fn new_post(arguments: &SaaruArguments, path: &Path, post: &NewPost) -> Result<PathBuf> {
+    let mut path = resolve_path(&arguments.source_dir, path);
+    if !path.starts_with(&arguments.source_dir) || path == arguments.source_dir {
+        return Err(SaaruError::config(
+            &path,
+            format!(
+                "posts go inside the source directory {:?}",
+                &arguments.source_dir
+            ),
+        ));
+    }
+    if path.extension().is_none() {
+        path.set_extension("md");
+    }
+    if path.exists() {
+        return Err(SaaruError::config(
+            &path,
+            "already exists, pick another path for the post",
+        ));
+    }
+
+    let template = post
+        .template
+        .as_deref()
+        .unwrap_or(&arguments.config.metadata.templates.default);
+    if !arguments.template_dir.join(template).is_file() {
+        log::warn!(
+            "Template {:?} isn't in the template directory {:?} yet",
+            template,
+            &arguments.template_dir
+        );
+    }
+
+    let frontmatter = NewPostFrontMatter {
+        title: &post.title,
+        description: post.description.as_deref().unwrap_or(""),
+        date: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+        tags: &post.tags,
+        collections: &post.collections,
+        template,
+        wip: post.draft,
+    };
+    let yaml = serde_yaml::to_string(&frontmatter)
+        .map_err(|e| SaaruError::config(&path, format!("couldn't write the frontmatter: {}", e)))?;
+    let content = format!("---\n{}---\n\n", yaml);
+
+    // Make sure the build will read back exactly what we're about to write
+    FrontMatter::from_markdown(&Matter::<YAML>::new(), &path, &content)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| SaaruError::io(parent, e))?;
+    }
+    fs::write(&path, content).map_err(|e| SaaruError::io(&path, e))?;
+    log::info!("Created {:?}", &path);
+    Ok(path)
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::arguments::{BuildOptions, DirectoryOverrides, SaaruArguments, ServerOverrides};
+use crate::check::CheckReport;
+use crate::error::{Result, SaaruError};
+use crate::saaru::SaaruInstance;
+use crate::scaffold::{self, NewPost};
+
+/// Everything needed to set up a site, mirroring the command line. Anything left
+/// out comes from the site's config file, or its defaults.
+#[derive(Debug)]
+pub struct SiteBuilder {
+    base_path: PathBuf,
+    config_file: Option<PathBuf>,
+    directories: DirectoryOverrides,
+    server: ServerOverrides,
+    options: BuildOptions,
+}
+
+impl Default for SiteBuilder {
+    fn default() -> Self {
+        SiteBuilder {
+            base_path: PathBuf::from("."),
+            config_file: None,
+            directories: DirectoryOverrides::default(),
+            server: ServerOverrides::default(),
+            options: BuildOptions::default(),
+        }
+    }
+}
+
+impl SiteBuilder {
+    /// The folder with the config file and the source, template and static directories
+    pub fn base_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.base_path = path.into();
+        self
+    }
+
+    /// Use this config file instead of looking for one in the base path
+    pub fn config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// Where the markdown pages are, instead of the config's `directories.source`
+    pub fn source_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.directories.source = Some(path.into());
+        self
+    }
+
+    /// Where the templates are, instead of the config's `directories.templates`
+    pub fn template_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.directories.templates = Some(path.into());
+        self
+    }
+
+    /// Where the static files are, instead of the config's `directories.static`
+    pub fn static_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.directories.static_files = Some(path.into());
+        self
+    }
+
+    /// Where the site is built, instead of the config's `directories.build`
+    pub fn build_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.directories.build = Some(path.into());
+        self
+    }
+
+    /// The address the live reload server listens on
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.server.host = Some(host.into());
+        self
+    }
+
+    /// The first port the live reload server tries, the next free one is used if it's taken
+    pub fn port(mut self, port: u16) -> Self {
+        self.server.port = Some(port);
+        self
+    }
+
+    /// Serve the site under this path, like `/my-project`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.server.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Serve the site and reload the browser on every change while watching
+    pub fn live_reload(mut self, live_reload: bool) -> Self {
+        self.options.live_reload = live_reload;
+        self
+    }
+
+    /// Only re-render on every change while watching, without serving the site
+    pub fn live_rerender(mut self, live_rerender: bool) -> Self {
+        self.options.live_rerender = live_rerender;
+        self
+    }
+
+    /// Carry on past broken files to report all of them at the end. The build still fails
+    /// as a whole, and nothing is written if any of them are broken.
+    pub fn keep_going(mut self, keep_going: bool) -> Self {
+        self.options.keep_going = keep_going;
+        self
+    }
+
+    /// Include pages marked `wip: true`
+    pub fn drafts(mut self, drafts: bool) -> Self {
+        self.options.drafts = drafts;
+        self
+    }
+
+    /// Ignore the build cache and render every page
+    pub fn no_cache(mut self, no_cache: bool) -> Self {
+        self.options.no_cache = no_cache;
+        self
+    }
+
+    /// Empty the build directory (except preserved files) before the first build
+    pub fn clean(mut self, clean: bool) -> Self {
+        self.options.clean = clean;
+        self
+    }
+
+    /// Read the config and get the site ready to render
+    pub fn build(self) -> Result<Site> {
+        if self.options.live_reload && self.options.live_rerender {
+            return Err(SaaruError::config(
+                &self.base_path,
+                "live reload and live re-render can't both be on, live reload already re-renders",
+            ));
+        }
+        let arguments = SaaruArguments::new(
+            self.base_path,
+            self.config_file,
+            self.directories,
+            self.server,
+            self.options,
+        )?;
+        let mut instance = SaaruInstance::new(arguments)?;
+        instance.set_template_environment();
+        Ok(Site { instance })
+    }
+
+    /// Add a post to the site at `path`, relative to its source directory, without building it
+    pub fn new_post(self, path: impl AsRef<Path>, post: &NewPost) -> Result<PathBuf> {
+        let arguments = SaaruArguments::new(
+            self.base_path,
+            self.config_file,
+            self.directories,
+            self.server,
+            self.options,
+        )?;
+        scaffold::new_post(&arguments, path.as_ref(), post)
+    }
+}
+
+/// A site, ready to be rendered
+pub struct Site {
+    instance: SaaruInstance,
+}
+
+impl Site {
+    /// Start setting up a site, with everything read from `.` and its config by default
+    pub fn builder() -> SiteBuilder {
+        SiteBuilder::default()
+    }
+
+    /// Build the whole site into the build directory
+    pub fn render(&mut self) -> Result<BuildReport> {
+        let start = Instant::now();
+        self.instance.render_pipeline()?;
+        Ok(self.instance.build_report(start.elapsed()))
+    }
+
+    /// Look for problems in the site without writing anything
+    pub fn check(&mut self) -> Result<CheckReport> {
+        self.instance.check()
+    }
+
+    /// Re-render on every change (and serve the site, with live reload on) until the process
+    /// is stopped. `failed_build` is why the last `render` failed, if it did, to show in the browser.
+    pub fn watch(self, failed_build: Option<SaaruError>) -> Result<()> {
+        self.instance.orchestrator(failed_build)
+    }
+}
+
+/// What a build did
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    /// Where the site was built
+    pub build_dir: PathBuf,
+    /// Markdown pages in the site, drafts left out unless they're included
+    pub pages: usize,
+    /// Everything generated: pages, tag and collection pages, feeds, the sitemap and so on
+    pub outputs: usize,
+    /// How many of those were written, the rest hadn't changed since the last build
+    pub rendered: usize,
+    /// Files in the static directory
+    pub static_files: usize,
+    /// How many static files were new or changed, and copied
+    pub static_copied: usize,
+    /// Files the last build wrote that this one no longer produces, and were deleted
+    pub pruned: usize,
+    /// How long the build took
+    pub duration: Duration,
+}
+
+impl fmt::Display for BuildReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Built {} pages into {} in {:?}: {} of {} outputs rendered, {} of {} static files copied",
+            self.pages,
+            self.build_dir.display(),
+            self.duration,
+            self.rendered,
+            self.outputs,
+            self.static_copied,
+            self.static_files
+        )?;
+        if self.pruned > 0 {
+            write!(f, ", {} stale files removed", self.pruned)?;
+        }
+        Ok(())
+    }
+}
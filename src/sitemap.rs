@@ -0,0 +1,57 @@
+use serde::Deserialize;
+
+use crate::feed::escape_xml;
+
+// The `sitemap` section of the site config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SitemapConfig {
+    // On by default whenever `metadata.base_url` is set
+    pub enabled: bool,
+    // Also write a robots.txt that points crawlers at the sitemap
+    pub robots_txt: bool,
+}
+
+impl Default for SitemapConfig {
+    fn default() -> Self {
+        SitemapConfig {
+            enabled: true,
+            robots_txt: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SitemapEntry {
+    // Absolute URL of the page
+    pub loc: String,
+    // W3C datetime of the last change, when we know it
+    pub lastmod: Option<String>,
+}
+
+// Turn a build-relative link into the URL the page is actually served at,
+// so `/posts/index.html` is listed as `/posts/`
+pub fn page_url(base_url: &str, link: &str) -> String {
+    let link = link.trim_start_matches('/');
+    let link = link.strip_suffix("index.html").unwrap_or(link);
+    format!("{}/{}", base_url, link)
+}
+
+pub fn render_sitemap(entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for entry in entries {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!("    <loc>{}</loc>\n", escape_xml(&entry.loc)));
+        if let Some(lastmod) = &entry.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+pub fn render_robots_txt(sitemap_url: &str) -> String {
+    format!("User-agent: *\nAllow: /\n\nSitemap: {}\n", sitemap_url)
+}
@@ -0,0 +1,298 @@
+use minijinja::Environment;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::cache::{hash_bytes, Fingerprint};
+use crate::error::{Result, SaaruError};
+
+// Which templates pull in which, following `extends`, `include`, `import` and `from`.
+// Lets Saaru work out which pages a template edit actually touches.
+#[derive(Debug, Default)]
+pub struct TemplateGraph {
+    // Template name (as the loader sees it) -> hash of its source
+    hashes: HashMap<String, String>,
+    // Template name -> templates it pulls in directly. `None` when one of them is
+    // only picked at render time, so it could be any of them.
+    references: HashMap<String, Option<Vec<String>>>,
+    // Templates that look at `base.tags` or `base.collections` themselves
+    listing_readers: HashSet<String>,
+    // Template name -> collections it looks up by name, like `base.collections["posts"]`
+    collections: HashMap<String, BTreeSet<String>>,
+}
+
+impl TemplateGraph {
+    pub fn scan(template_dir: &Path) -> Result<Self> {
+        let mut graph = TemplateGraph::default();
+        for entry in WalkDir::new(template_dir) {
+            let entry = entry.map_err(|e| {
+                let path = e.path().unwrap_or(template_dir).to_path_buf();
+                SaaruError::io(path, e.into())
+            })?;
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            let name = match template_name(template_dir, entry.path()) {
+                Some(name) => name,
+                None => continue,
+            };
+            let content = fs::read(entry.path()).map_err(|e| SaaruError::io(entry.path(), e))?;
+            let source = String::from_utf8_lossy(&content);
+            if reads_listings(&source) {
+                graph.listing_readers.insert(name.clone());
+            }
+            graph
+                .references
+                .insert(name.clone(), referenced_templates(&source));
+            graph
+                .collections
+                .insert(name.clone(), referenced_collections(&source));
+            graph.hashes.insert(name, hash_bytes(&content));
+        }
+        Ok(graph)
+    }
+
+    // Every template `name` renders through, itself included
+    pub fn dependencies(&self, name: &str) -> BTreeSet<String> {
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![name.to_string()];
+        while let Some(current) = pending.pop() {
+            if !seen.insert(current.clone()) {
+                continue;
+            }
+            match self.references.get(&current) {
+                Some(Some(references)) => pending.extend(references.iter().cloned()),
+                // Can't tell what it includes, so it might as well include everything
+                Some(None) => {
+                    seen.extend(self.references.keys().cloned());
+                    break;
+                }
+                None => {}
+            }
+        }
+        seen
+    }
+
+    // Whether anything `name` renders through shows the tag or collection listings,
+    // which means it has to be rendered again whenever any page's frontmatter changes
+    pub fn reads_listings(&self, name: &str) -> bool {
+        self.dependencies(name)
+            .iter()
+            .any(|template| self.listing_readers.contains(template))
+    }
+
+    // Every (template, collection) pair where the template looks up the collection by name
+    pub fn referenced_collections(&self) -> BTreeSet<(&str, &str)> {
+        self.collections
+            .iter()
+            .flat_map(|(template, collections)| {
+                collections
+                    .iter()
+                    .map(move |collection| (template.as_str(), collection.as_str()))
+            })
+            .collect()
+    }
+
+    // Hash the sources of every template `name` renders through into `fingerprint`
+    pub fn add_to_fingerprint(&self, name: &str, fingerprint: &mut Fingerprint) {
+        for dependency in self.dependencies(name) {
+            let hash = self
+                .hashes
+                .get(&dependency)
+                .map_or("missing", String::as_str);
+            fingerprint.add(&dependency).add(hash);
+        }
+    }
+}
+
+// The name the template loader knows a file in the template directory by
+fn template_name(template_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(template_dir).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+// Whether a template uses `base.tags` or `base.collections`. Handing `base` around
+// whole (or a template that doesn't parse) counts, since there's no telling what it reads.
+fn reads_listings(source: &str) -> bool {
+    let env = Environment::new();
+    let template = match env.template_from_str(source) {
+        Ok(template) => template,
+        Err(_) => return true,
+    };
+    template.undeclared_variables(true).iter().any(|variable| {
+        variable == "base"
+            || variable.starts_with("base.tags")
+            || variable.starts_with("base.collections")
+    })
+}
+
+// The templates a template pulls in through `{% extends %}`, `{% include %}`,
+// `{% import %}` and `{% from %}` tags. `None` if any of them isn't a plain
+// string (or list of strings), since then it's only known at render time.
+fn referenced_templates(source: &str) -> Option<Vec<String>> {
+    let mut references = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{%") {
+        let after = &rest[start + 2..];
+        let end = match after.find("%}") {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = after[..end].trim_matches(|c: char| c == '-' || c == '+' || c.is_whitespace());
+        rest = &after[end + 2..];
+
+        let (keyword, arguments) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        // Only the part naming the template matters, not `as name` or `import a, b`
+        let expression = match keyword {
+            "extends" | "include" => arguments,
+            "import" => arguments.split(" as ").next().unwrap_or_default(),
+            "from" => arguments.split(" import ").next().unwrap_or_default(),
+            _ => continue,
+        };
+
+        let (names, leftover) = string_literals(expression);
+        let leftover_is_static = leftover
+            .split(|c: char| c.is_whitespace() || c == '[' || c == ']' || c == ',')
+            .all(|word| {
+                matches!(
+                    word,
+                    "" | "ignore" | "missing" | "with" | "without" | "context"
+                )
+            });
+        if names.is_empty() || !leftover_is_static {
+            return None;
+        }
+        references.extend(names);
+    }
+    Some(references)
+}
+
+// The collections a template looks up by name, either as `base.collections.posts` or
+// as `base.collections["posts"]`. Lookups through a variable can't be known up front.
+fn referenced_collections(source: &str) -> BTreeSet<String> {
+    let mut collections = BTreeSet::new();
+    for (start, _) in source.match_indices("base.collections") {
+        let rest = &source[start + "base.collections".len()..];
+        let name = if let Some(attribute) = rest.strip_prefix('.') {
+            let name = attribute
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .next()
+                .unwrap_or_default();
+            // `base.collections.items()` and friends are methods, not collections
+            if attribute[name.len()..].starts_with('(') {
+                continue;
+            }
+            name.to_string()
+        } else if let Some(subscript) = rest.strip_prefix('[') {
+            let subscript = subscript.trim_start();
+            if !subscript.starts_with(['"', '\'']) {
+                continue;
+            }
+            let end = subscript.find(']').unwrap_or(subscript.len());
+            let (literals, leftover) = string_literals(&subscript[..end]);
+            match literals.as_slice() {
+                [literal] if leftover.trim().is_empty() => literal.clone(),
+                _ => continue,
+            }
+        } else {
+            continue;
+        };
+        if !name.is_empty() {
+            collections.insert(name);
+        }
+    }
+    collections
+}
+
+// Split quoted strings out of a template expression, returning them and whatever was left
+fn string_literals(expression: &str) -> (Vec<String>, String) {
+    let mut literals = Vec::new();
+    let mut leftover = String::new();
+    let mut characters = expression.chars();
+    while let Some(character) = characters.next() {
+        if character != '"' && character != '\'' {
+            leftover.push(character);
+            continue;
+        }
+        let mut literal = String::new();
+        while let Some(next) = characters.next() {
+            match next {
+                '\\' => literal.extend(characters.next()),
+                next if next == character => break,
+                next => literal.push(next),
+            }
+        }
+        literals.push(literal);
+        // Keep the words on either side of the string apart
+        leftover.push(' ');
+    }
+    (literals, leftover)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_every_kind_of_reference() {
+        let source = r#"
+            {% extends "base.jinja" %}
+            {%- include 'footer.jinja' -%}
+            {% include ["a.jinja", "b.jinja"] ignore missing %}
+            {% import "macros.jinja" as macros %}
+            {% from "forms.jinja" import input, label %}
+            {% block body %}{{ title }}{% endblock %}
+        "#;
+        assert_eq!(
+            referenced_templates(source),
+            Some(vec![
+                "base.jinja".to_string(),
+                "footer.jinja".to_string(),
+                "a.jinja".to_string(),
+                "b.jinja".to_string(),
+                "macros.jinja".to_string(),
+                "forms.jinja".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn dynamic_references_are_unknown() {
+        assert_eq!(referenced_templates("{% include some_var %}"), None);
+        assert_eq!(
+            referenced_templates(r#"{% include "pages/" ~ name %}"#),
+            None
+        );
+        assert_eq!(referenced_templates(r#"{% extends layout %}"#), None);
+    }
+
+    #[test]
+    fn splits_out_string_literals() {
+        let (literals, leftover) = string_literals(r#"["a.jinja", 'it\'s.jinja'] ignore missing"#);
+        assert_eq!(literals, vec!["a.jinja", "it's.jinja"]);
+        assert_eq!(
+            leftover.split_whitespace().collect::<Vec<_>>(),
+            vec!["[", ",", "]", "ignore", "missing"]
+        );
+    }
+
+    #[test]
+    fn finds_collections_looked_up_by_name() {
+        let source = r#"
+            {% for post in base.collections.posts %}{% endfor %}
+            {% for note in base.collections["notes"] %}{% endfor %}
+            {% for name, pages in base.collections.items() %}{% endfor %}
+            {% for page in base.collections[name] %}{% endfor %}
+        "#;
+        let expected: BTreeSet<String> = ["notes", "posts"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        assert_eq!(referenced_collections(source), expected);
+    }
+}
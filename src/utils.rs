@@ -1,18 +1,78 @@
-use std::path::Path;
-use std::{fs, io};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
 
-
-// Copy files from source to destination recursively.
-pub fn copy_recursively(source: impl AsRef<Path>, destination: impl AsRef<Path>) -> io::Result<()> {
-    fs::create_dir_all(&destination)?;
-    for entry in fs::read_dir(source)? {
-        let entry = entry?;
-        let filetype = entry.file_type()?;
-        if filetype.is_dir() {
-            copy_recursively(entry.path(), destination.as_ref().join(entry.file_name()))?;
-        } else {
-            fs::copy(entry.path(), destination.as_ref().join(entry.file_name()))?;
+// Resolve `path` against `base` and tidy up any `.` and `..` components, without
+// touching the filesystem (the path might not exist yet).
+pub fn resolve_path(base: &Path, path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
         }
     }
-    Ok(())
+    resolved
+}
+
+// Whether `path` stays inside whatever directory it's joined onto: no `..`, and no
+// root or drive prefix that would replace the directory altogether
+pub fn is_contained(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+// Scratch files editors write while saving: vim's swap files and its `4913` probe,
+// emacs lock files and autosaves, `~` backups, JetBrains' safe-write copies and so on.
+// Changes to these never need a rebuild.
+pub fn is_editor_temp_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    name.ends_with('~')
+        || name == "4913"
+        || name == ".DS_Store"
+        || name.starts_with(".#")
+        || (name.len() > 1 && name.starts_with('#') && name.ends_with('#'))
+        || name.starts_with(".goutputstream-")
+        || name.ends_with("___jb_tmp___")
+        || name.ends_with("___jb_old___")
+        || matches!(
+            extension,
+            Some("swp" | "swo" | "swx" | "swpx" | "tmp" | "crswap")
+        )
+}
+
+// A path next to `path`, like `.build.saaru-staging` for `build`
+pub fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.saaru-{}", name, suffix))
+}
+
+// Write a file through a temporary file next to it that's renamed into place, so
+// nobody ever reads half of it, and hard links to the old file keep the old contents
+pub fn replace_file(path: &Path, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let temporary = sibling_path(path, "tmp");
+    write(&temporary)
+        .and_then(|_| fs::rename(&temporary, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })
+}
+
+// Hard link a file, or copy it where the filesystem can't do that
+pub fn link_or_copy(from: &Path, to: &Path) -> io::Result<()> {
+    fs::hard_link(from, to).or_else(|_| fs::copy(from, to).map(|_| ()))
+}
+
+pub fn remove_dir_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        removed => removed,
+    }
 }